@@ -0,0 +1,46 @@
+//! Compares sequential hashing with `Generator::hash_many_parallel`.
+//!
+//! Run with `cargo bench --features rayon`.
+
+use std::{
+  hint::black_box,
+  time::{Duration, Instant},
+};
+
+use gravatar_rs::Generator;
+
+/// How many times each measurement is repeated, the fastest run is reported.
+const RUNS: usize = 10;
+
+/// Returns the fastest of [`RUNS`] runs of a function.
+fn fastest<T>(mut function: impl FnMut() -> T) -> Duration {
+  (0..RUNS)
+    .map(|_| {
+      let start = Instant::now();
+      black_box(function());
+      start.elapsed()
+    })
+    .min()
+    .unwrap_or_default()
+}
+
+fn main() {
+  for count in [1_000, 10_000, 100_000, 1_000_000] {
+    let emails = (0..count)
+      .map(|index| format!("user-{index}@example.com"))
+      .collect::<Vec<_>>();
+
+    let sequential = fastest(|| {
+      emails
+        .iter()
+        .map(|email| Generator::hash_email(email))
+        .collect::<Vec<_>>()
+    });
+    let parallel = fastest(|| Generator::hash_many_parallel(&emails));
+
+    println!(
+      "{count:>9} emails: sequential {sequential:>12?}, \
+       parallel {parallel:>12?}"
+    );
+  }
+}
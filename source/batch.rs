@@ -0,0 +1,240 @@
+//! Generating URLs for many emails at once.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::Generator;
+
+/// The number of emails below which [`Generator::hash_many_parallel`] hashes
+/// sequentially.
+#[cfg(feature = "rayon")]
+const PARALLEL_THRESHOLD: usize = 4096;
+
+/// A plan of every unique URL to fetch for pre-populating a cache, see
+/// [`Generator::cache_warm_plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheWarmPlan {
+  /// The number of URLs that were requested before deduplication.
+  pub requested: usize,
+
+  /// The unique URLs to fetch, in the order they were first generated.
+  pub urls: Vec<String>,
+}
+
+impl CacheWarmPlan {
+  /// Returns the number of duplicate URLs that were removed.
+  pub fn duplicates(&self) -> usize {
+    self.requested - self.urls.len()
+  }
+}
+
+impl Generator {
+  /// Generates a URL for every `(email, size override)` request, applying
+  /// the size override on top of the Generator's configuration when present.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let urls = Generator::default().resolve_batch(&[
+  ///   ("helllo@holllo.cc".to_string(), None),
+  ///   ("helllo@holllo.cc".to_string(), Some(128)),
+  /// ]);
+  ///
+  /// assert!(urls[1].ends_with("?s=128"));
+  /// ```
+  pub fn resolve_batch(
+    &self,
+    requests: &[(String, Option<i32>)],
+  ) -> Vec<String> {
+    requests
+      .iter()
+      .map(|(email, size)| match size {
+        Some(size) => self.clone().set_image_size(*size).generate(email),
+        None => self.generate(email),
+      })
+      .collect()
+  }
+
+  /// Generates a [`CacheWarmPlan`] with every unique URL for all the
+  /// combinations of emails and sizes. Emails that normalize to the same hash
+  /// and repeated sizes are only included once.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let plan = Generator::default().cache_warm_plan(
+  ///   &["helllo@holllo.cc", "HELLLO@holllo.cc"],
+  ///   &[64, 128],
+  /// );
+  ///
+  /// assert_eq!(plan.urls.len(), 2);
+  /// assert_eq!(plan.duplicates(), 2);
+  /// ```
+  pub fn cache_warm_plan(
+    &self,
+    emails: &[&str],
+    sizes: &[i32],
+  ) -> CacheWarmPlan {
+    let mut seen = HashSet::new();
+    let mut urls = vec![];
+
+    for size in sizes {
+      let generator = self.clone().set_image_size(*size);
+
+      for email in emails {
+        let url = generator.generate(email);
+        if seen.insert(url.clone()) {
+          urls.push(url);
+        }
+      }
+    }
+
+    CacheWarmPlan {
+      requested: emails.len() * sizes.len(),
+      urls,
+    }
+  }
+
+  /// Hashes many emails across all available threads with [`rayon`],
+  /// returning the hashes in the same order as the emails. Lists shorter
+  /// than a few thousand emails are hashed sequentially, as spreading them
+  /// over threads costs more than it saves. See [`Generator::hash_email`].
+  ///
+  /// This requires the `rayon` feature.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let hashes = Generator::hash_many_parallel(&["helllo@holllo.cc"]);
+  ///
+  /// assert_eq!(hashes, ["ebff9105dce4954b1bdb57fdab079ff3"]);
+  /// ```
+  #[cfg(feature = "rayon")]
+  pub fn hash_many_parallel<S: AsRef<str> + Sync>(emails: &[S]) -> Vec<String> {
+    use rayon::prelude::*;
+
+    if emails.len() < PARALLEL_THRESHOLD {
+      return emails
+        .iter()
+        .map(|email| Self::hash_email(email.as_ref()))
+        .collect();
+    }
+
+    emails
+      .par_iter()
+      .map(|email| Self::hash_email(email.as_ref()))
+      .collect()
+  }
+
+  /// Generates a URL for every email and returns them as a JSON object
+  /// mapping each email, as given, to its URL. Repeated emails are only
+  /// included once.
+  ///
+  /// This requires the `serde` feature.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let map = Generator::default().generate_json_map(&["helllo@holllo.cc"]);
+  ///
+  /// assert_eq!(
+  ///   map["helllo@holllo.cc"],
+  ///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  /// );
+  /// ```
+  #[cfg(feature = "serde")]
+  pub fn generate_json_map<S: AsRef<str>>(
+    &self,
+    emails: &[S],
+  ) -> serde_json::Value {
+    let map = emails
+      .iter()
+      .map(|email| {
+        let email = email.as_ref();
+        (email.to_string(), self.generate(email).into())
+      })
+      .collect::<serde_json::Map<_, _>>();
+
+    serde_json::Value::Object(map)
+  }
+
+  /// Generates a URL for every email and writes each of them followed by
+  /// `separator` to an async writer, without collecting them first.
+  ///
+  /// This requires the `tokio` feature.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// # tokio_test().unwrap();
+  /// # #[tokio::main(flavor = "current_thread")]
+  /// # async fn tokio_test() -> std::io::Result<()> {
+  /// let mut output = vec![];
+  /// Generator::default()
+  ///   .write_urls(&["helllo@holllo.cc"], &mut output, "\n")
+  ///   .await?;
+  ///
+  /// assert_eq!(
+  ///   output,
+  ///   b"https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\n"
+  /// );
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "tokio")]
+  pub async fn write_urls<W, S>(
+    &self,
+    emails: &[S],
+    writer: &mut W,
+    separator: &str,
+  ) -> std::io::Result<()>
+  where
+    W: tokio::io::AsyncWrite + Unpin,
+    S: AsRef<str>,
+  {
+    use tokio::io::AsyncWriteExt;
+
+    for email in emails {
+      writer
+        .write_all(self.generate(email.as_ref()).as_bytes())
+        .await?;
+      writer.write_all(separator.as_bytes()).await?;
+    }
+
+    writer.flush().await
+  }
+
+  /// Groups the indices of emails that result in the same avatar after
+  /// normalization. Only groups with at least two emails are returned, in the
+  /// order of their first email.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let duplicates = Generator::find_duplicate_avatars(&[
+  ///   "helllo@holllo.cc",
+  ///   "me@bauke.xyz",
+  ///   " HELLLO@holllo.cc",
+  /// ]);
+  ///
+  /// assert_eq!(duplicates, vec![vec![0, 2]]);
+  /// ```
+  pub fn find_duplicate_avatars<S: AsRef<str>>(
+    emails: &[S],
+  ) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_indices = HashMap::new();
+
+    for (index, email) in emails.iter().enumerate() {
+      let hash = Self::hash_email(email.as_ref());
+      let group = *group_indices.entry(hash).or_insert_with(|| {
+        groups.push(vec![]);
+        groups.len() - 1
+      });
+      groups[group].push(index);
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+  }
+}
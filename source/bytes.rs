@@ -0,0 +1,398 @@
+//! A compact binary representation of a [`Generator`]'s configuration.
+//!
+//! The format starts with the magic bytes `GRV` and a version byte, followed
+//! by a record for every option that differs from "unset". Each record is a
+//! one byte tag, a big-endian `u32` length and that many bytes of value.
+//! Strings are stored as UTF-8, numbers as big-endian and booleans are only
+//! stored when they're `true`, with an empty value.
+
+use crate::{Error, Generator, HashDisplay, PathStyle};
+
+/// The magic bytes every representation starts with.
+const MAGIC: &[u8] = b"GRV";
+
+/// The version of the format.
+const VERSION: u8 = 2;
+
+/// The tags identifying each option.
+mod tag {
+  /// The tag for [`crate::Generator::base_url`].
+  pub const BASE_URL: u8 = 1;
+  /// The tag for [`crate::Generator::default_image`].
+  pub const DEFAULT_IMAGE: u8 = 2;
+  /// The tag for [`crate::Generator::default_image_size`].
+  pub const DEFAULT_IMAGE_SIZE: u8 = 3;
+  /// The tag for [`crate::Generator::density_descriptors`].
+  pub const DENSITY_DESCRIPTORS: u8 = 4;
+  /// The tag for [`crate::Generator::dimensions`].
+  pub const DIMENSIONS: u8 = 5;
+  /// The tag for [`crate::Generator::force_default`].
+  pub const FORCE_DEFAULT: u8 = 6;
+  /// The tag for [`crate::Generator::force_requires_default`].
+  pub const FORCE_REQUIRES_DEFAULT: u8 = 7;
+  /// The tag for [`crate::Generator::fragment`].
+  pub const FRAGMENT: u8 = 8;
+  /// The tag for [`crate::Generator::image_size`].
+  pub const IMAGE_SIZE: u8 = 9;
+  /// The tag for [`crate::Generator::include_file_extension`].
+  pub const INCLUDE_FILE_EXTENSION: u8 = 10;
+  /// The tag for [`crate::Generator::param_prefix`].
+  pub const PARAM_PREFIX: u8 = 11;
+  /// The tag for [`crate::Generator::private`].
+  pub const PRIVATE: u8 = 12;
+  /// The tag for [`crate::Generator::rating`].
+  pub const RATING: u8 = 13;
+  /// The tag for [`crate::Generator::force_jpeg`].
+  pub const FORCE_JPEG: u8 = 14;
+  /// The tag for [`crate::Generator::size_cap`].
+  pub const SIZE_CAP: u8 = 15;
+  /// The tag for [`crate::Generator::retina_suffix`].
+  pub const RETINA_SUFFIX: u8 = 16;
+  /// The tag for [`crate::Generator::scheme`].
+  pub const SCHEME: u8 = 17;
+  /// The tag for [`crate::Generator::tenant_salt`].
+  pub const TENANT_SALT: u8 = 18;
+  /// The tag for [`crate::Generator::experiment`].
+  pub const EXPERIMENT: u8 = 19;
+  /// The tag for [`crate::Generator::url_template`].
+  pub const URL_TEMPLATE: u8 = 20;
+  /// The tag for [`crate::Generator::path_style`].
+  pub const PATH_STYLE: u8 = 21;
+  /// The tag for [`crate::Generator::crop_mode`].
+  pub const CROP_MODE: u8 = 22;
+  /// The tag for [`crate::Generator::hash_length`].
+  pub const HASH_LENGTH: u8 = 23;
+  /// The tag for [`crate::Generator::csp_nonce`].
+  pub const CSP_NONCE: u8 = 24;
+  /// The tag for [`crate::Generator::strip_metadata`].
+  pub const STRIP_METADATA: u8 = 25;
+  /// The tag for [`crate::Generator::hash_display`].
+  pub const HASH_DISPLAY: u8 = 26;
+  /// The tag for [`crate::Generator::request_original`].
+  pub const REQUEST_ORIGINAL: u8 = 27;
+  /// The tag for [`crate::Generator::fallback_pool`], written once per URL.
+  pub const FALLBACK_POOL: u8 = 28;
+  /// The tag for [`crate::Generator::encoding_safe_chars`].
+  pub const ENCODING_SAFE_CHARS: u8 = 29;
+  /// The tag for [`crate::Generator::crawler_user_agents`], written once per
+  /// substring.
+  pub const CRAWLER_USER_AGENT: u8 = 30;
+  /// The tag for [`crate::Generator::lazy_loading`].
+  pub const LAZY_LOADING: u8 = 31;
+  /// The tag for [`crate::Generator::extension_separator`].
+  pub const EXTENSION_SEPARATOR: u8 = 32;
+}
+
+impl Generator {
+  /// Serializes the Generator's configuration into a compact binary
+  /// representation, see [`crate::Generator::from_bytes`].
+  ///
+  /// The [`Hasher`](crate::Hasher) can't be serialized, so restored
+  /// Generators always use the default [`Md5Hasher`](crate::Md5Hasher). The
+  /// local fallback directory is specific to a machine and isn't serialized
+  /// either.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let bytes = Generator::default().set_image_size(128).to_bytes();
+  /// let generator = Generator::from_bytes(&bytes)?;
+  ///
+  /// assert_eq!(generator.image_size, Some(128));
+  /// # Ok::<(), gravatar_rs::Error>(())
+  /// ```
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+
+    let mut write = |tag: u8, value: &[u8]| {
+      bytes.push(tag);
+      let length = u32::try_from(value.len())
+        .expect("option values are shorter than 4 GiB");
+      bytes.extend_from_slice(&length.to_be_bytes());
+      bytes.extend_from_slice(value);
+    };
+
+    write(tag::BASE_URL, self.base_url.as_bytes());
+
+    for crawler in &self.crawler_user_agents {
+      write(tag::CRAWLER_USER_AGENT, crawler.as_bytes());
+    }
+
+    if let Some(crop_mode) = self.crop_mode {
+      write(tag::CROP_MODE, crop_mode.as_str().as_bytes());
+    }
+
+    if let Some(nonce) = &self.csp_nonce {
+      write(tag::CSP_NONCE, nonce.as_bytes());
+    }
+
+    if let Some(default_image) = &self.default_image {
+      write(tag::DEFAULT_IMAGE, default_image.as_bytes());
+    }
+
+    if let Some(size) = self.default_image_size {
+      write(tag::DEFAULT_IMAGE_SIZE, &size.to_be_bytes());
+    }
+
+    let densities = self.density_descriptors.iter();
+    write(
+      tag::DENSITY_DESCRIPTORS,
+      &densities
+        .flat_map(|density| density.to_be_bytes())
+        .collect::<Vec<_>>(),
+    );
+
+    if let Some((width, height)) = self.dimensions {
+      write(
+        tag::DIMENSIONS,
+        &[width.to_be_bytes(), height.to_be_bytes()].concat(),
+      );
+    }
+
+    if !self.encoding_safe_chars.is_empty() {
+      write(
+        tag::ENCODING_SAFE_CHARS,
+        self.encoding_safe_chars.as_bytes(),
+      );
+    }
+
+    if let Some(experiment) = &self.experiment {
+      write(tag::EXPERIMENT, experiment.as_bytes());
+    }
+
+    if self.extension_separator != "." {
+      write(
+        tag::EXTENSION_SEPARATOR,
+        self.extension_separator.as_bytes(),
+      );
+    }
+
+    for default_image in &self.fallback_pool {
+      write(tag::FALLBACK_POOL, default_image.as_bytes());
+    }
+
+    if self.force_default {
+      write(tag::FORCE_DEFAULT, &[]);
+    }
+
+    if self.force_jpeg {
+      write(tag::FORCE_JPEG, &[]);
+    }
+
+    if self.force_requires_default {
+      write(tag::FORCE_REQUIRES_DEFAULT, &[]);
+    }
+
+    if let Some(fragment) = &self.fragment {
+      write(tag::FRAGMENT, fragment.as_bytes());
+    }
+
+    if self.hash_display != HashDisplay::default() {
+      let HashDisplay {
+        group_size,
+        uppercase,
+      } = self.hash_display;
+      let group_size = group_size.map_or(0, |size| size as u32);
+      write(
+        tag::HASH_DISPLAY,
+        &[&[u8::from(uppercase)], &group_size.to_be_bytes()[..]].concat(),
+      );
+    }
+
+    if let Some(length) = self.hash_length {
+      write(tag::HASH_LENGTH, &(length as u32).to_be_bytes());
+    }
+
+    if let Some(size) = self.image_size {
+      write(tag::IMAGE_SIZE, &size.to_be_bytes());
+    }
+
+    if self.include_file_extension {
+      write(tag::INCLUDE_FILE_EXTENSION, &[]);
+    }
+
+    if self.lazy_loading {
+      write(tag::LAZY_LOADING, &[]);
+    }
+
+    write(tag::PARAM_PREFIX, self.param_prefix.as_bytes());
+
+    match &self.path_style {
+      PathStyle::Flat => {}
+      PathStyle::Sharded => write(tag::PATH_STYLE, &[1]),
+      PathStyle::Bucketed(bucket) => {
+        write(tag::PATH_STYLE, &[&[2], bucket.as_bytes()].concat());
+      }
+    }
+
+    if self.private {
+      write(tag::PRIVATE, &[]);
+    }
+
+    if let Some(rating) = &self.rating {
+      write(tag::RATING, rating.as_bytes());
+    }
+
+    if self.retina_suffix {
+      write(tag::RETINA_SUFFIX, &[]);
+    }
+
+    write(tag::SCHEME, self.scheme.as_bytes());
+
+    if let Some(size_cap) = self.size_cap {
+      write(tag::SIZE_CAP, &size_cap.to_be_bytes());
+    }
+
+    if self.strip_metadata {
+      write(tag::STRIP_METADATA, &[]);
+    }
+
+    if self.request_original {
+      write(tag::REQUEST_ORIGINAL, &[]);
+    }
+
+    if let Some(tenant_salt) = &self.tenant_salt {
+      write(tag::TENANT_SALT, tenant_salt.as_bytes());
+    }
+
+    if let Some(url_template) = &self.url_template {
+      write(tag::URL_TEMPLATE, url_template.as_bytes());
+    }
+
+    bytes
+  }
+
+  /// Deserializes a Generator from the binary representation created by
+  /// [`crate::Generator::to_bytes`].
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+    let invalid = |reason: &str| Error::InvalidBytes(reason.to_string());
+
+    let rest = bytes
+      .strip_prefix(MAGIC)
+      .ok_or_else(|| invalid("missing magic bytes"))?;
+    let (&version, mut rest) = rest
+      .split_first()
+      .ok_or_else(|| invalid("missing version"))?;
+    if version != VERSION {
+      return Err(invalid(&format!("unsupported version {version}")));
+    }
+
+    let mut generator = Self {
+      base_url: String::new(),
+      crawler_user_agents: vec![],
+      density_descriptors: vec![],
+      ..Self::default()
+    };
+
+    while let Some((&tag, remaining)) = rest.split_first() {
+      let (length, remaining) = remaining
+        .split_first_chunk::<4>()
+        .ok_or_else(|| invalid("truncated record length"))?;
+      let length = u32::from_be_bytes(*length) as usize;
+      let value = remaining
+        .get(..length)
+        .ok_or_else(|| invalid("truncated record value"))?;
+      rest = &remaining[length..];
+
+      let string = || {
+        String::from_utf8(value.to_vec()).map_err(|_| invalid("invalid UTF-8"))
+      };
+      let number =
+        || <[u8; 4]>::try_from(value).map_err(|_| invalid("invalid number"));
+
+      match tag {
+        tag::BASE_URL => generator.base_url = string()?,
+        tag::CRAWLER_USER_AGENT => {
+          generator.crawler_user_agents.push(string()?);
+        }
+        tag::CROP_MODE => {
+          let crop_mode = string()?
+            .parse()
+            .map_err(|_| invalid("invalid crop mode"))?;
+          generator.crop_mode = Some(crop_mode);
+        }
+        tag::CSP_NONCE => generator.csp_nonce = Some(string()?),
+        tag::DEFAULT_IMAGE => generator.default_image = Some(string()?),
+        tag::DEFAULT_IMAGE_SIZE => {
+          generator.default_image_size = Some(u32::from_be_bytes(number()?));
+        }
+        tag::DENSITY_DESCRIPTORS => {
+          if value.len() % 4 != 0 {
+            return Err(invalid("invalid density descriptors"));
+          }
+
+          generator.density_descriptors = value
+            .chunks_exact(4)
+            .map(|chunk| {
+              f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+            })
+            .collect();
+        }
+        tag::DIMENSIONS => {
+          let (width, height) = value
+            .split_at_checked(4)
+            .filter(|(_, height)| height.len() == 4)
+            .ok_or_else(|| invalid("invalid dimensions"))?;
+          generator.dimensions = Some((
+            u32::from_be_bytes(width.try_into().unwrap()),
+            u32::from_be_bytes(height.try_into().unwrap()),
+          ));
+        }
+        tag::ENCODING_SAFE_CHARS => generator.encoding_safe_chars = string()?,
+        tag::EXPERIMENT => generator.experiment = Some(string()?),
+        tag::EXTENSION_SEPARATOR => generator.extension_separator = string()?,
+        tag::FALLBACK_POOL => generator.fallback_pool.push(string()?),
+        tag::FORCE_DEFAULT => generator.force_default = true,
+        tag::FORCE_JPEG => generator.force_jpeg = true,
+        tag::FORCE_REQUIRES_DEFAULT => generator.force_requires_default = true,
+        tag::FRAGMENT => generator.fragment = Some(string()?),
+        tag::HASH_DISPLAY => {
+          let Some((&uppercase, group_size)) = value.split_first() else {
+            return Err(invalid("invalid hash display"));
+          };
+          let group_size = <[u8; 4]>::try_from(group_size)
+            .map_err(|_| invalid("invalid hash display"))?;
+          let group_size = u32::from_be_bytes(group_size) as usize;
+          generator.hash_display = HashDisplay::new(
+            (group_size != 0).then_some(group_size),
+            uppercase != 0,
+          );
+        }
+        tag::HASH_LENGTH => {
+          generator.hash_length = Some(u32::from_be_bytes(number()?) as usize);
+        }
+        tag::IMAGE_SIZE => {
+          generator.image_size = Some(i32::from_be_bytes(number()?));
+        }
+        tag::INCLUDE_FILE_EXTENSION => generator.include_file_extension = true,
+        tag::LAZY_LOADING => generator.lazy_loading = true,
+        tag::PARAM_PREFIX => generator.param_prefix = string()?,
+        tag::PATH_STYLE => {
+          generator.path_style = match value.split_first() {
+            Some((1, [])) => PathStyle::Sharded,
+            Some((2, bucket)) => PathStyle::Bucketed(
+              String::from_utf8(bucket.to_vec())
+                .map_err(|_| invalid("invalid UTF-8"))?,
+            ),
+            _ => return Err(invalid("invalid path style")),
+          };
+        }
+        tag::PRIVATE => generator.private = true,
+        tag::RATING => generator.rating = Some(string()?),
+        tag::REQUEST_ORIGINAL => generator.request_original = true,
+        tag::RETINA_SUFFIX => generator.retina_suffix = true,
+        tag::SCHEME => generator.scheme = string()?,
+        tag::SIZE_CAP => {
+          generator.size_cap = Some(i32::from_be_bytes(number()?));
+        }
+        tag::STRIP_METADATA => generator.strip_metadata = true,
+        tag::TENANT_SALT => generator.tenant_salt = Some(string()?),
+        tag::URL_TEMPLATE => generator.url_template = Some(string()?),
+        _ => return Err(invalid(&format!("unknown tag {tag}"))),
+      }
+    }
+
+    Ok(generator)
+  }
+}
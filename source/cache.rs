@@ -0,0 +1,63 @@
+//! Populating in-process caches with generated URLs.
+
+use std::collections::HashMap;
+
+use crate::Generator;
+
+/// A cache that generated URLs can be stored in, see
+/// [`Generator::generate_and_cache`].
+///
+/// ```rust
+/// use gravatar_rs::{AvatarCache, Generator};
+///
+/// #[derive(Default)]
+/// struct Urls(Vec<String>);
+///
+/// impl AvatarCache for Urls {
+///   fn put(&mut self, _key: String, url: String) {
+///     self.0.push(url);
+///   }
+/// }
+///
+/// let mut cache = Urls::default();
+/// Generator::default().generate_and_cache("helllo@holllo.cc", &mut cache);
+///
+/// assert_eq!(cache.0.len(), 1);
+/// ```
+pub trait AvatarCache {
+  /// Stores a generated URL under its cache key.
+  fn put(&mut self, key: String, url: String);
+}
+
+impl AvatarCache for HashMap<String, String> {
+  fn put(&mut self, key: String, url: String) {
+    self.insert(key, url);
+  }
+}
+
+impl Generator {
+  /// Generates a URL like [`Generator::generate`] and stores it in `cache`
+  /// under the same key [`Generator::etag_for`] is derived from, so the cache
+  /// is invalidated whenever an option changes.
+  ///
+  /// ```rust
+  /// use std::collections::HashMap;
+  ///
+  /// use gravatar_rs::Generator;
+  ///
+  /// let mut cache = HashMap::new();
+  /// let url = Generator::default()
+  ///   .generate_and_cache("helllo@holllo.cc", &mut cache);
+  ///
+  /// assert!(cache.values().any(|cached| cached == &url));
+  /// ```
+  pub fn generate_and_cache<C: AvatarCache>(
+    &self,
+    email: &str,
+    cache: &mut C,
+  ) -> String {
+    let url = self.generate(email);
+    cache.put(self.cache_key(email), url.clone());
+    url
+  }
+}
@@ -0,0 +1,119 @@
+//! Colors and seeds derived from email hashes, for local placeholders.
+
+use crate::{normalize_email, Generator};
+
+impl Generator {
+  /// Derives a color from the first three bytes of the email's hash, so the
+  /// same email always gets the same color.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(Generator::avatar_color("helllo@holllo.cc"), (235, 255, 145));
+  /// ```
+  pub fn avatar_color(email: &str) -> (u8, u8, u8) {
+    let hash = md5::compute(normalize_email(email));
+    (hash[0], hash[1], hash[2])
+  }
+
+  /// Derives a seed from the first eight bytes of the email's hash, for
+  /// varying skeleton or shimmer animations per avatar. The same email always
+  /// gets the same seed.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(
+  ///   Generator::placeholder_seed("helllo@holllo.cc"),
+  ///   Generator::placeholder_seed(" HELLLO@holllo.cc "),
+  /// );
+  /// ```
+  pub fn placeholder_seed(email: &str) -> u64 {
+    let hash = md5::compute(normalize_email(email));
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(bytes)
+  }
+
+  /// Derives a palette of `count` related but distinct colors from the email's
+  /// hash, by rotating a hash-derived hue in small steps. The same email
+  /// always gets the same palette.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let palette = Generator::avatar_color_palette("helllo@holllo.cc", 3);
+  ///
+  /// assert_eq!(palette.len(), 3);
+  /// ```
+  pub fn avatar_color_palette(email: &str, count: usize) -> Vec<(u8, u8, u8)> {
+    let hash = md5::compute(normalize_email(email));
+    let hue = f32::from(u16::from_be_bytes([hash[0], hash[1]]) % 360);
+    let step = (360.0 / count.max(1) as f32).min(30.0);
+
+    (0..count)
+      .map(|index| hsl_to_rgb(hue + step * index as f32, 0.65, 0.5))
+      .collect()
+  }
+
+  /// Derives a soft pastel color from the email's hash, with a hash-derived
+  /// hue at high lightness and moderate saturation, which reads well behind
+  /// dark initials. The same email always gets the same color.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(Generator::pastel_color("helllo@holllo.cc"), (233, 198, 236));
+  /// ```
+  pub fn pastel_color(email: &str) -> (u8, u8, u8) {
+    let hash = md5::compute(normalize_email(email));
+    let hue = f32::from(u16::from_be_bytes([hash[0], hash[1]]) % 360);
+    hsl_to_rgb(hue, 0.5, 0.85)
+  }
+
+  /// Generates a CSS snippet with the email's [`Generator::avatar_color`] as a
+  /// custom property named `--<var_name>` and the avatar as the background
+  /// image.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let css = Generator::default()
+  ///   .generate_with_color_var("helllo@holllo.cc", "avatar-accent");
+  ///
+  /// assert_eq!(
+  ///   css,
+  ///   "--avatar-accent: #ebff91; background-image: \
+  ///    url(\"https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\");"
+  /// );
+  /// ```
+  pub fn generate_with_color_var(&self, email: &str, var_name: &str) -> String {
+    let (red, green, blue) = Self::avatar_color(email);
+    let url = self.generate(email);
+    format!(
+      "--{var_name}: #{red:02x}{green:02x}{blue:02x}; \
+       background-image: url(\"{url}\");"
+    )
+  }
+}
+
+/// Converts a color from HSL to RGB, with the hue in degrees and the
+/// saturation and lightness between `0.0` and `1.0`.
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (u8, u8, u8) {
+  let hue = hue.rem_euclid(360.0) / 60.0;
+  let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+  let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+
+  let (red, green, blue) = match hue as u8 {
+    0 => (chroma, x, 0.0),
+    1 => (x, chroma, 0.0),
+    2 => (0.0, chroma, x),
+    3 => (0.0, x, chroma),
+    4 => (x, 0.0, chroma),
+    _ => (chroma, 0.0, x),
+  };
+
+  let offset = lightness - chroma / 2.0;
+  let to_byte = |value: f32| ((value + offset) * 255.0).round() as u8;
+  (to_byte(red), to_byte(green), to_byte(blue))
+}
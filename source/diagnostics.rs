@@ -0,0 +1,141 @@
+//! A support-friendly report of how a [`Generator`] handles an email.
+
+use std::sync::Arc;
+
+use crate::{normalize_email, Generator, TokenProvider};
+
+impl Generator {
+  /// Returns a multi-line report for pasting into support tickets, with the
+  /// redacted email, its hash, the generated URL, every option that differs
+  /// from the default and any [warnings](Generator::warnings).
+  ///
+  /// The email is redacted to its first character and domain, the CSP nonce
+  /// and tenant salt are only reported as set, and the token in the URL is
+  /// replaced with `REDACTED` without calling the token provider.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let report = Generator::default()
+  ///   .set_image_size(128)
+  ///   .diagnostics("helllo@holllo.cc");
+  ///
+  /// assert!(report.contains("email: h***@holllo.cc\n"));
+  /// assert!(report.contains("  image_size: Some(128)\n"));
+  /// ```
+  pub fn diagnostics(&self, email: &str) -> String {
+    let default = Self::default();
+
+    // Formats every listed option with its `Debug` representation and keeps
+    // the ones that differ from the default.
+    macro_rules! changed_options {
+      ($($field:ident),* $(,)?) => {
+        [$((
+          stringify!($field),
+          format!("{:?}", self.$field),
+          format!("{:?}", default.$field),
+        )),*]
+        .into_iter()
+        .filter(|(_, value, default)| value != default)
+        .map(|(name, value, _)| (name, value))
+        .collect::<Vec<_>>()
+      };
+    }
+
+    let mut options = changed_options!(
+      base_url,
+      crawler_user_agents,
+      crop_mode,
+      default_image,
+      default_image_size,
+      density_descriptors,
+      dimensions,
+      encoding_safe_chars,
+      experiment,
+      extension_separator,
+      fallback_pool,
+      force_default,
+      force_jpeg,
+      force_requires_default,
+      fragment,
+      hash_display,
+      hash_length,
+      hasher,
+      image_size,
+      include_file_extension,
+      lazy_loading,
+      local_fallback_dir,
+      param_prefix,
+      path_style,
+      private,
+      rating,
+      request_original,
+      retina_suffix,
+      scheme,
+      size_cap,
+      strip_metadata,
+      token_provider,
+      url_template,
+    );
+    if self.csp_nonce.is_some() {
+      options.push(("csp_nonce", "<set>".to_string()));
+    }
+    if self.tenant_salt.is_some() {
+      options.push(("tenant_salt", "<set>".to_string()));
+    }
+    options.sort_by_key(|(name, _)| *name);
+
+    let redacted = Self {
+      token_provider: self
+        .token_provider
+        .as_ref()
+        .map(|_| TokenProvider(Arc::new(|| "REDACTED".to_string()))),
+      ..self.clone()
+    };
+
+    let mut report = vec![
+      format!("email: {}", redact_email(email)),
+      format!("hash: {}", self.hash(email)),
+      format!("url: {}", redacted.generate(email)),
+    ];
+
+    if options.is_empty() {
+      report.push("options: none".to_string());
+    } else {
+      report.push("options:".to_string());
+      report.extend(
+        options
+          .into_iter()
+          .map(|(name, value)| format!("  {name}: {value}")),
+      );
+    }
+
+    let warnings = self.warnings();
+    if warnings.is_empty() {
+      report.push("warnings: none".to_string());
+    } else {
+      report.push("warnings:".to_string());
+      report.extend(warnings.into_iter().map(|warning| format!("  {warning}")));
+    }
+
+    report.join("\n") + "\n"
+  }
+}
+
+/// Redacts a normalized email to the first character of its local part and
+/// its domain, like `h***@holllo.cc`.
+fn redact_email(email: &str) -> String {
+  let email = normalize_email(email);
+  let (local_part, domain) = email.split_once('@').unwrap_or((&email, ""));
+  let first = local_part
+    .chars()
+    .next()
+    .map(String::from)
+    .unwrap_or_default();
+
+  if domain.is_empty() {
+    format!("{first}***")
+  } else {
+    format!("{first}***@{domain}")
+  }
+}
@@ -0,0 +1,155 @@
+//! Error types used by the crate.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::{MAX_IMAGE_SIZE, MIN_IMAGE_SIZE};
+
+/// An error that can happen while using a [`Generator`](crate::Generator).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+  /// The hash in a generated URL doesn't match the email's hash.
+  HashMismatch {
+    /// The hash found in the URL.
+    actual: String,
+
+    /// The hash of the email.
+    expected: String,
+  },
+
+  /// A binary Generator representation couldn't be deserialized.
+  InvalidBytes(String),
+
+  /// A URL couldn't be parsed.
+  InvalidUrl(String),
+
+  /// An I/O error, for example while reading a file.
+  Io(std::io::Error),
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Self::HashMismatch { actual, expected } => {
+        write!(f, "expected hash {expected} but found {actual}")
+      }
+      Self::InvalidBytes(reason) => write!(f, "invalid bytes: {reason}"),
+      Self::InvalidUrl(url) => write!(f, "invalid URL {url:?}"),
+      Self::Io(error) => write!(f, "I/O error: {error}"),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Io(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl From<std::io::Error> for Error {
+  fn from(error: std::io::Error) -> Self {
+    Self::Io(error)
+  }
+}
+
+/// A problem with a [`Generator`](crate::Generator)'s configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GeneratorError {
+  /// The base URL is empty.
+  EmptyBaseUrl,
+
+  /// The default image is forced without setting one, while
+  /// [`Generator::set_force_requires_default`](crate::Generator::set_force_requires_default)
+  /// is enabled.
+  ForceDefaultWithoutDefaultImage,
+
+  /// The bucket of [`PathStyle::Bucketed`](crate::PathStyle::Bucketed) is
+  /// empty or contains characters that aren't unreserved URL characters.
+  InvalidBucket(String),
+
+  /// The crop mode isn't one of `center`, `face` or `entropy`.
+  InvalidCropMode(String),
+
+  /// The default image isn't a known keyword or an absolute HTTP(S) URL.
+  InvalidDefaultImage(String),
+
+  /// The width or height is outside of the range Gravatar supports.
+  InvalidDimensions(u32, u32),
+
+  /// The extension separator is empty or contains characters that aren't
+  /// unreserved URL characters.
+  InvalidExtensionSeparator(String),
+
+  /// The hash length is zero, odd or longer than the full hash.
+  InvalidHashLength(usize),
+
+  /// The image size is outside of the range Gravatar supports.
+  InvalidImageSize(i32),
+
+  /// The rating isn't one of `g`, `pg`, `r` or `x`.
+  InvalidRating(String),
+
+  /// The scheme isn't `https` or `http`.
+  InvalidScheme(String),
+
+  /// The size cap is outside of the range Gravatar supports.
+  InvalidSizeCap(i32),
+
+  /// The URL template doesn't contain the `{hash}` placeholder.
+  InvalidUrlTemplate(String),
+
+  /// The original image is requested while an image size or dimensions are
+  /// set too.
+  OriginalWithImageSize,
+}
+
+impl Display for GeneratorError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Self::EmptyBaseUrl => write!(f, "the base URL is empty"),
+      Self::ForceDefaultWithoutDefaultImage => {
+        write!(f, "the default image is forced but not set")
+      }
+      Self::InvalidBucket(bucket) => write!(f, "unsafe bucket {bucket:?}"),
+      Self::InvalidCropMode(crop_mode) => {
+        write!(f, "unknown crop mode {crop_mode:?}")
+      }
+      Self::InvalidDefaultImage(default_image) => {
+        write!(f, "invalid default image {default_image:?}")
+      }
+      Self::InvalidDimensions(width, height) => write!(
+        f,
+        "dimensions {width}x{height} are outside of \
+         {MIN_IMAGE_SIZE}..={MAX_IMAGE_SIZE}"
+      ),
+      Self::InvalidExtensionSeparator(separator) => {
+        write!(f, "unsafe extension separator {separator:?}")
+      }
+      Self::InvalidHashLength(length) => {
+        write!(f, "hash length {length} is zero, odd or too long")
+      }
+      Self::InvalidImageSize(size) => write!(
+        f,
+        "image size {size} is outside of {MIN_IMAGE_SIZE}..={MAX_IMAGE_SIZE}"
+      ),
+      Self::InvalidRating(rating) => write!(f, "unknown rating {rating:?}"),
+      Self::InvalidScheme(scheme) => write!(f, "unsupported scheme {scheme:?}"),
+      Self::InvalidSizeCap(size_cap) => write!(
+        f,
+        "size cap {size_cap} is outside of {MIN_IMAGE_SIZE}..={MAX_IMAGE_SIZE}"
+      ),
+      Self::InvalidUrlTemplate(template) => {
+        write!(f, "URL template {template:?} is missing {{hash}}")
+      }
+      Self::OriginalWithImageSize => {
+        write!(f, "the original image is requested with an image size")
+      }
+    }
+  }
+}
+
+impl std::error::Error for GeneratorError {}
@@ -0,0 +1,61 @@
+//! Pluggable hash functions for emails.
+
+use std::fmt::Debug;
+
+use sha2::{Digest, Sha256};
+
+/// A hash function used to turn a normalized email into the hash in image
+/// URLs, see [`Generator::set_hasher`](crate::Generator::set_hasher).
+///
+/// ```rust
+/// use gravatar_rs::{Generator, Hasher};
+///
+/// #[derive(Debug)]
+/// struct Reversed;
+///
+/// impl Hasher for Reversed {
+///   fn hash(&self, normalized_email: &str) -> String {
+///     normalized_email.chars().rev().collect()
+///   }
+/// }
+///
+/// let generator = Generator::default().set_hasher(Box::new(Reversed));
+///
+/// assert_eq!(
+///   generator.generate("helllo@holllo.cc"),
+///   "https://www.gravatar.com/avatar/cc.ollloh@ollleh"
+/// );
+/// ```
+pub trait Hasher: Debug + Send + Sync {
+  /// Hashes an email that has already been trimmed and lowercased.
+  fn hash(&self, normalized_email: &str) -> String;
+}
+
+/// The MD5 hash function Gravatar uses, the default [`Hasher`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Md5Hasher;
+
+impl Hasher for Md5Hasher {
+  fn hash(&self, normalized_email: &str) -> String {
+    format!("{:x}", md5::compute(normalized_email))
+  }
+}
+
+/// The SHA-256 hash function Libravatar supports alongside MD5.
+///
+/// ```rust
+/// use gravatar_rs::{Hasher, Sha256Hasher};
+///
+/// assert_eq!(
+///   Sha256Hasher.hash("helllo@holllo.cc").len(),
+///   64
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+  fn hash(&self, normalized_email: &str) -> String {
+    format!("{:x}", Sha256::digest(normalized_email))
+  }
+}
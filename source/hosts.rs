@@ -0,0 +1,82 @@
+//! The hosts a [`Generator`] may cause clients to contact.
+
+use crate::Generator;
+
+/// The host Gravatar serves profiles from.
+const GRAVATAR_PROFILE_HOST: &str = "en.gravatar.com";
+
+impl Generator {
+  /// Returns every host a client may contact for the Generator's avatars,
+  /// for building `img-src` and `connect-src` Content Security Policies.
+  ///
+  /// This includes the avatar host and the hosts of default image URLs,
+  /// including the ones in the fallback pool. With `include_profile`, the
+  /// profile host is included as well, which is `en.gravatar.com` for
+  /// Gravatar and the avatar host for other services.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let hosts = Generator::default()
+  ///   .set_default_image("https://example.com/default.png")
+  ///   .hosts_for_plan(true);
+  ///
+  /// assert_eq!(hosts, ["www.gravatar.com", "example.com", "en.gravatar.com"]);
+  /// ```
+  pub fn hosts_for_plan(&self, include_profile: bool) -> Vec<String> {
+    let avatar_host = host(&self.base_url);
+    let mut hosts = vec![avatar_host.clone()];
+
+    let default_images = self.default_image.iter().chain(&self.fallback_pool);
+    for default_image in default_images {
+      if let Some(rest) = default_image
+        .strip_prefix("https://")
+        .or_else(|| default_image.strip_prefix("http://"))
+      {
+        hosts.push(host(rest));
+      }
+    }
+
+    if include_profile {
+      if avatar_host == "gravatar.com" || avatar_host.ends_with(".gravatar.com")
+      {
+        hosts.push(GRAVATAR_PROFILE_HOST.to_string());
+      } else {
+        hosts.push(avatar_host);
+      }
+    }
+
+    let mut unique = vec![];
+    for host in hosts {
+      if !host.is_empty() && !unique.contains(&host) {
+        unique.push(host);
+      }
+    }
+
+    unique
+  }
+
+  /// Returns the hosts to add `<link rel="dns-prefetch">` hints for, so pages
+  /// can resolve them before any avatar is requested. These are the avatar
+  /// host, the hosts of default images and the profile host, like
+  /// [`Generator::hosts_for_plan`] with the profile included.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(
+  ///   Generator::default().dns_prefetch_hosts(),
+  ///   ["www.gravatar.com", "en.gravatar.com"]
+  /// );
+  /// ```
+  pub fn dns_prefetch_hosts(&self) -> Vec<String> {
+    self.hosts_for_plan(true)
+  }
+}
+
+/// Returns the lowercased host of a URL without its scheme.
+fn host(url: &str) -> String {
+  let authority = url.split(['/', '?', '#']).next().unwrap_or_default();
+  let host = authority.rsplit('@').next().unwrap_or_default();
+  host.to_lowercase()
+}
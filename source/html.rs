@@ -0,0 +1,343 @@
+//! HTML elements for generated avatars.
+
+use crate::{
+  monogram::monogram_svg, Generator, DEFAULT_IMAGE_SIZE, MAX_IMAGE_SIZE,
+};
+
+impl Generator {
+  /// Generates an AMP `<amp-img>` element for the email's avatar, with the
+  /// width and height taken from the configured size or Gravatar's default of
+  /// 80 pixels.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let element = Generator::default()
+  ///   .set_image_size(128)
+  ///   .generate_amp_img("helllo@holllo.cc", "Holllo's avatar");
+  ///
+  /// assert_eq!(
+  ///   element,
+  ///   "<amp-img src=\"https://www.gravatar.com/avatar/\
+  ///    ebff9105dce4954b1bdb57fdab079ff3?s=128\" width=\"128\" \
+  ///    height=\"128\" alt=\"Holllo&#39;s avatar\" layout=\"responsive\">\
+  ///    </amp-img>"
+  /// );
+  /// ```
+  pub fn generate_amp_img(&self, email: &str, alt: &str) -> String {
+    let src = escape_html(&self.generate(email));
+    let (width, height) = self.display_dimensions();
+    let alt = escape_html(alt);
+
+    format!(
+      "<amp-img src=\"{src}\" width=\"{width}\" height=\"{height}\" \
+       alt=\"{alt}\" layout=\"responsive\"></amp-img>"
+    )
+  }
+
+  /// Generates a JSON-LD [`ImageObject`] for the email's avatar, with the
+  /// width and height taken from the configured size or Gravatar's default of
+  /// 80 pixels.
+  ///
+  /// [`ImageObject`]: https://schema.org/ImageObject
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let json = Generator::default().generate_jsonld("helllo@holllo.cc");
+  ///
+  /// assert!(json.starts_with(r#"{"@context":"https://schema.org","#));
+  /// assert!(json.ends_with(r#""width":80,"height":80}"#));
+  /// ```
+  pub fn generate_jsonld(&self, email: &str) -> String {
+    let content_url = escape_json(&self.generate(email));
+    let (width, height) = self.display_dimensions();
+
+    format!(
+      "{{\"@context\":\"https://schema.org\",\"@type\":\"ImageObject\",\
+       \"contentUrl\":\"{content_url}\",\"width\":{width},\"height\":{height}}}"
+    )
+  }
+
+  /// Generates a `srcset` attribute value with an entry for every configured
+  /// [density descriptor](Generator::set_density_descriptors). Each entry
+  /// scales the configured size, or Gravatar's default of 80 pixels, by its
+  /// density and clamps it to the maximum size Gravatar supports.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let srcset = Generator::default()
+  ///   .set_image_size(64)
+  ///   .generate_srcset("helllo@holllo.cc");
+  ///
+  /// assert_eq!(
+  ///   srcset,
+  ///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?s=64 1x, \
+  ///    https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?s=128 2x"
+  /// );
+  /// ```
+  pub fn generate_srcset(&self, email: &str) -> String {
+    let scale = |size: f32, density: f32| {
+      ((size * density).round() as i32).clamp(1, MAX_IMAGE_SIZE)
+    };
+
+    let entries = self.density_descriptors.iter().map(|&density| {
+      let generator = match self.dimensions {
+        Some((width, height)) => self.clone().set_dimensions(
+          scale(width as f32, density) as u32,
+          scale(height as f32, density) as u32,
+        ),
+        None => {
+          let size = self.image_size.unwrap_or(DEFAULT_IMAGE_SIZE);
+          self.clone().set_image_size(scale(size as f32, density))
+        }
+      };
+
+      format!("{} {density}x", generator.generate(email))
+    });
+
+    entries.collect::<Vec<_>>().join(", ")
+  }
+
+  /// Generates an `<img>` element for the email's avatar, with the width and
+  /// height taken from the configured size or Gravatar's default of 80
+  /// pixels, and a `nonce` attribute when [`Generator::set_csp_nonce`] is set.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let element = Generator::default()
+  ///   .set_csp_nonce(Some("r4nd0m"))
+  ///   .generate_img_tag("helllo@holllo.cc", "Holllo");
+  ///
+  /// assert!(element.ends_with(r#"alt="Holllo" nonce="r4nd0m">"#));
+  /// ```
+  pub fn generate_img_tag(&self, email: &str, alt: &str) -> String {
+    self.img_tag(email, &[("alt", alt)])
+  }
+
+  /// Generates an `<img>` element for lazy loading with JavaScript, where the
+  /// URL is in `data-src` instead of `src`, followed by a `<noscript>` block
+  /// with a plain `<img>` so the avatar also shows up without JavaScript.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let html = Generator::default()
+  ///   .generate_with_noscript("helllo@holllo.cc", "Holllo");
+  ///
+  /// assert!(html.starts_with("<img data-src=\"https://www.gravatar.com/"));
+  /// assert!(html.contains("<noscript><img src=\"https://www.gravatar.com/"));
+  /// ```
+  pub fn generate_with_noscript(&self, email: &str, alt: &str) -> String {
+    let scripted = self.img_tag_with_src_attribute(
+      "data-src",
+      email,
+      &[("class", "lazyload"), ("alt", alt)],
+    );
+    let plain = self.img_tag(email, &[("alt", alt)]);
+
+    format!("{scripted}<noscript>{plain}</noscript>")
+  }
+
+  /// Generates an accessible `<img>` element for the email's avatar.
+  ///
+  /// Decorative avatars, like ones next to a visible username, get an empty
+  /// `alt` and `aria-hidden="true"` so screen readers skip them. Other
+  /// avatars get `role="img"` with `alt` and `aria-label` set to `alt`.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let element = Generator::default()
+  ///   .generate_accessible_img("helllo@holllo.cc", "Holllo", true);
+  ///
+  /// assert!(element.ends_with(r#"alt="" aria-hidden="true">"#));
+  /// ```
+  pub fn generate_accessible_img(
+    &self,
+    email: &str,
+    alt: &str,
+    decorative: bool,
+  ) -> String {
+    if decorative {
+      self.img_tag(email, &[("alt", ""), ("aria-hidden", "true")])
+    } else {
+      self.img_tag(email, &[("alt", alt), ("role", "img"), ("aria-label", alt)])
+    }
+  }
+
+  /// Generates an `<object>` element for the email's avatar at `size` pixels,
+  /// with an inline monogram SVG of `name`'s initials as fallback content
+  /// that browsers show when the image fails to load.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let element = Generator::default().generate_object_with_fallback(
+  ///   "helllo@holllo.cc",
+  ///   "Holllo Helllo",
+  ///   64,
+  /// );
+  ///
+  /// assert!(element.starts_with("<object data=\"https://www.gravatar.com/"));
+  /// assert!(element.contains(">HH</text></svg></object>"));
+  /// ```
+  pub fn generate_object_with_fallback(
+    &self,
+    email: &str,
+    name: &str,
+    size: u32,
+  ) -> String {
+    let image_size = size.min(MAX_IMAGE_SIZE as u32) as i32;
+    let data =
+      escape_html(&self.clone().set_image_size(image_size).generate(email));
+    let fallback = monogram_svg(name, Self::avatar_color(email), size);
+
+    format!(
+      "<object data=\"{data}\" type=\"image/jpeg\" width=\"{size}\" \
+       height=\"{size}\">{fallback}</object>"
+    )
+  }
+
+  /// Builds an `<img>` element for the email's avatar with the `src`, `width`
+  /// and `height` attributes, followed by the given attributes, the lazy
+  /// loading attributes if enabled and the CSP nonce if one is set. All
+  /// attribute values are escaped.
+  pub(crate) fn img_tag(
+    &self,
+    email: &str,
+    attributes: &[(&str, &str)],
+  ) -> String {
+    self.img_tag_with_src_attribute("src", email, attributes)
+  }
+
+  /// Builds an `<img>` element like [`Generator::img_tag`], with the URL in
+  /// `src_attribute` instead of `src`.
+  fn img_tag_with_src_attribute(
+    &self,
+    src_attribute: &str,
+    email: &str,
+    attributes: &[(&str, &str)],
+  ) -> String {
+    let src = escape_html(&self.generate(email));
+    let (width, height) = self.display_dimensions();
+    let mut tag = format!(
+      "<img {src_attribute}=\"{src}\" width=\"{width}\" height=\"{height}\""
+    );
+
+    for (name, value) in attributes {
+      tag.push_str(&format!(" {name}=\"{}\"", escape_html(value)));
+    }
+
+    if self.lazy_loading {
+      tag.push_str(" loading=\"lazy\" decoding=\"async\"");
+    }
+
+    if let Some(nonce) = &self.csp_nonce {
+      tag.push_str(&format!(" nonce=\"{}\"", escape_html(nonce)));
+    }
+
+    tag.push('>');
+    tag
+  }
+
+  /// Generates a `sizes` attribute value from `(max viewport width, image
+  /// width)` breakpoints, to go with [`Generator::generate_srcset`].
+  ///
+  /// Breakpoints are sorted by viewport width and each gets a
+  /// `(max-width: <viewport>px) <image>px` entry, except the widest one whose
+  /// image width is used as the fallback for all larger viewports.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(
+  ///   Generator::sizes_attr(&[(1200, 96), (600, 48)]),
+  ///   "(max-width: 600px) 48px, 96px"
+  /// );
+  /// ```
+  pub fn sizes_attr(breakpoints: &[(u32, u32)]) -> String {
+    let mut breakpoints = breakpoints.to_vec();
+    breakpoints.sort_by_key(|(viewport, _)| *viewport);
+
+    let Some(((_, fallback), breakpoints)) = breakpoints.split_last() else {
+      return String::new();
+    };
+
+    breakpoints
+      .iter()
+      .map(|(viewport, width)| format!("(max-width: {viewport}px) {width}px"))
+      .chain(std::iter::once(format!("{fallback}px")))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  /// Returns the aspect ratio of the avatar, which is `1.0` unless
+  /// [`Generator::set_dimensions`] configured a non-square size.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(Generator::default().aspect_ratio(), 1.0);
+  /// assert_eq!(Generator::default().set_dimensions(160, 80).aspect_ratio(), 2.0);
+  /// ```
+  pub fn aspect_ratio(&self) -> f32 {
+    match self.dimensions {
+      Some((width, height)) if height != 0 => width as f32 / height as f32,
+      _ => 1.0,
+    }
+  }
+
+  /// Returns the width and height the avatar will be displayed at.
+  pub(crate) fn display_dimensions(&self) -> (i64, i64) {
+    match (self.effective_dimensions(), self.effective_image_size()) {
+      (Some((width, height)), _) => (width.into(), height.into()),
+      (None, Some(size)) => (size.into(), size.into()),
+      (None, None) => (DEFAULT_IMAGE_SIZE.into(), DEFAULT_IMAGE_SIZE.into()),
+    }
+  }
+}
+
+/// Escapes the characters that have a special meaning in HTML text and
+/// attribute values.
+pub(crate) fn escape_html(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+
+  for char in value.chars() {
+    match char {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(char),
+    }
+  }
+
+  escaped
+}
+
+/// Escapes a value for use inside a JSON string.
+pub(crate) fn escape_json(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+
+  for char in value.chars() {
+    match char {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      char if char.is_control() => {
+        escaped.push_str(&format!("\\u{:04x}", u32::from(char)));
+      }
+      // Prevent the JSON from closing a surrounding `<script>` element.
+      '<' => escaped.push_str("\\u003c"),
+      _ => escaped.push(char),
+    }
+  }
+
+  escaped
+}
@@ -0,0 +1,87 @@
+//! Generating URLs for identities other than a single email.
+
+use crate::{normalize_email, Generator, Hasher, Sha256Hasher};
+
+impl Generator {
+  /// Generates a URL for a contact identified by an email, a phone number or
+  /// both, returning `None` when neither is given.
+  ///
+  /// Emails are normalized like in [`Generator::hash_email`] and phone
+  /// numbers are reduced to their digits, keeping a leading `+`. When both
+  /// are given they're combined as `<email>|<phone>` before hashing, so a
+  /// given pair always results in the same URL.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default();
+  ///
+  /// assert_eq!(
+  ///   generator.generate_combined_identity(Some("helllo@holllo.cc"), None),
+  ///   Some(generator.generate("helllo@holllo.cc"))
+  /// );
+  /// ```
+  pub fn generate_combined_identity(
+    &self,
+    email: Option<&str>,
+    phone: Option<&str>,
+  ) -> Option<String> {
+    let identity =
+      match (email.map(normalize_email), phone.map(normalize_phone)) {
+        (Some(email), Some(phone)) => format!("{email}|{phone}"),
+        (Some(email), None) => email,
+        (None, Some(phone)) => phone,
+        (None, None) => return None,
+      };
+
+    let hash = self.hash_normalized(&identity);
+    Some(self.build_url(&self.base_url, &hash))
+  }
+
+  /// Generates a URL for an ActivityPub actor, hashing its URI with SHA-256
+  /// like Libravatar does for SHA-256 email hashes.
+  ///
+  /// The URI is normalized by trimming it and lowercasing its scheme and
+  /// host, since those are case-insensitive. The path is kept as-is because
+  /// servers may treat it case-sensitively.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default().set_base_url("cdn.libravatar.org");
+  ///
+  /// assert_eq!(
+  ///   generator.generate_actor("https://Example.social/users/Holllo"),
+  ///   generator.generate_actor("https://example.social/users/Holllo"),
+  /// );
+  /// ```
+  pub fn generate_actor(&self, actor_uri: &str) -> String {
+    let hash = Sha256Hasher.hash(&normalize_actor_uri(actor_uri));
+    self.build_url(&self.base_url, &hash)
+  }
+}
+
+/// Normalizes an ActivityPub actor URI by trimming it and lowercasing its
+/// scheme and host, while keeping the case of the path.
+fn normalize_actor_uri(actor_uri: &str) -> String {
+  let actor_uri = actor_uri.trim();
+  let Some((scheme, rest)) = actor_uri.split_once("://") else {
+    return actor_uri.to_string();
+  };
+
+  let host_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+  let (host, path) = rest.split_at(host_end);
+  format!("{}://{}{path}", scheme.to_lowercase(), host.to_lowercase())
+}
+
+/// Normalizes a phone number by keeping only its digits and a leading `+`.
+fn normalize_phone(phone: &str) -> String {
+  let phone = phone.trim();
+  let digits = phone.chars().filter(char::is_ascii_digit);
+
+  if phone.starts_with('+') {
+    std::iter::once('+').chain(digits).collect()
+  } else {
+    digits.collect()
+  }
+}
@@ -27,13 +27,96 @@
 //!
 //! For all possible options see [`Generator`].
 
+use std::{borrow::Cow, path::PathBuf, sync::Arc};
+
+mod batch;
+mod bytes;
+mod cache;
+mod color;
+mod diagnostics;
+mod error;
+mod hasher;
+mod hosts;
+mod html;
+mod identity;
+mod local;
+mod locale;
+mod manifest;
+mod metrics;
+mod monogram;
+mod parse;
+mod query;
+mod render;
+mod round_robin;
+mod sealed;
+mod session;
+
+pub use batch::CacheWarmPlan;
+pub use cache::AvatarCache;
+pub use error::{Error, GeneratorError};
+pub use hasher::{Hasher, Md5Hasher, Sha256Hasher};
+pub use metrics::GenerateMetrics;
+pub use parse::ParsedUrl;
+pub use query::{GeneratorOptions, QueryParameters};
+pub use render::RenderInfo;
+pub use round_robin::RoundRobinGenerator;
+pub use sealed::SealedGenerator;
+pub use session::Session;
+
+/// The default image keywords Gravatar supports.
+pub(crate) const DEFAULT_IMAGES: [&str; 8] = [
+  "404",
+  "mp",
+  "identicon",
+  "monsterid",
+  "wavatar",
+  "retro",
+  "robohash",
+  "blank",
+];
+
+/// The image size Gravatar uses when none is specified.
+pub(crate) const DEFAULT_IMAGE_SIZE: i32 = 80;
+
+/// The minimum image size Gravatar supports.
+pub(crate) const MIN_IMAGE_SIZE: i32 = 1;
+
+/// The maximum image size Gravatar supports.
+pub(crate) const MAX_IMAGE_SIZE: i32 = 2048;
+
+/// The ratings Gravatar supports, from least to most explicit.
+pub(crate) const RATINGS: [&str; 4] = ["g", "pg", "r", "x"];
+
+/// The user agent substrings [`Generator::generate_for_user_agent`] treats
+/// as crawlers by default.
+const DEFAULT_CRAWLER_USER_AGENTS: [&str; 6] = [
+  "bot",
+  "crawler",
+  "spider",
+  "slurp",
+  "facebookexternalhit",
+  "scrapy",
+];
+
 /// A generator for Gravatar image URLs.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct Generator {
   /// The base URL for images, defaults to `www.gravatar.com`.
   pub base_url: String,
 
+  /// Lowercase substrings that mark a user agent as a crawler for
+  /// [`Generator::generate_for_user_agent`], defaults to common crawler
+  /// markers like `bot` and `spider`.
+  pub crawler_user_agents: Vec<String>,
+
+  /// A smart-crop mode for mirrors that support `crop=`, defaults to `None`.
+  pub crop_mode: Option<CropMode>,
+
+  /// A Content Security Policy nonce for generated HTML elements, defaults to
+  /// `None`.
+  pub csp_nonce: Option<String>,
+
   /// Which default image to use when there is no matching Gravatar, defaults
   /// to `None`.
   ///
@@ -42,33 +125,304 @@ pub struct Generator {
   /// [Gravatar documentation]: https://gravatar.com/site/implement/images/#default-image
   pub default_image: Option<String>,
 
+  /// A size to add to the default image when it's a URL, defaults to `None`.
+  pub default_image_size: Option<u32>,
+
+  /// The pixel densities [`Generator::generate_srcset`] creates entries for,
+  /// defaults to `1x` and `2x`.
+  pub density_descriptors: Vec<f32>,
+
+  /// A custom width and height for images, defaults to `None`. When set, the
+  /// `w=` and `h=` parameters are used instead of `s=`.
+  pub dimensions: Option<(u32, u32)>,
+
+  /// Extra characters to leave unencoded in the default image, defaults to
+  /// an empty string.
+  pub encoding_safe_chars: String,
+
+  /// An experiment flag for segmenting analytics, defaults to `None`.
+  pub experiment: Option<String>,
+
+  /// The separator between the hash and the file extension, defaults to `.`.
+  pub extension_separator: String,
+
+  /// Default image URLs to pick from per email instead of a single default
+  /// image, defaults to an empty list.
+  pub fallback_pool: Vec<String>,
+
   /// Whether you always want the default image to be returned, defaults to
   /// `false`.
   pub force_default: bool,
 
+  /// Whether JPEG images should be requested explicitly by always including
+  /// the `.jpg` file extension, defaults to `false`.
+  pub force_jpeg: bool,
+
+  /// Whether `f=y` should only be emitted when a default image is set,
+  /// defaults to `false`.
+  pub force_requires_default: bool,
+
+  /// A fragment to append to the end of the URL, defaults to `None`.
+  pub fragment: Option<String>,
+
+  /// How [`Generator::display_hash`] formats hashes, defaults to the plain
+  /// lowercase hash.
+  pub hash_display: HashDisplay,
+
+  /// A length to truncate hashes to in URLs, for legacy mirrors that use
+  /// short hashes, defaults to `None`.
+  pub hash_length: Option<usize>,
+
+  /// The hash function used for emails, defaults to [`Md5Hasher`].
+  pub hasher: Arc<dyn Hasher>,
+
   /// A custom size for images, defaults to `None`.
   pub image_size: Option<i32>,
 
   /// Whether to include `.jpg` in the image URL, defaults to false.
   pub include_file_extension: bool,
 
+  /// Whether generated `<img>` elements are lazy-loaded, defaults to `false`.
+  pub lazy_loading: bool,
+
+  /// A directory with locally cached avatars for
+  /// [`Generator::url_or_local_file`], defaults to `None`.
+  pub local_fallback_dir: Option<PathBuf>,
+
+  /// A prefix added to every query parameter key, defaults to an empty
+  /// string.
+  pub param_prefix: String,
+
+  /// The layout of the path after the base URL, defaults to
+  /// [`PathStyle::Flat`].
+  pub path_style: PathStyle,
+
+  /// Whether generated URLs should be treated as private and not stored by
+  /// shared caches, defaults to `false`.
+  pub private: bool,
+
   /// Which rating should be allowed, defaults to `None`.
   ///
   /// See the [Gravatar documentation] for all the possible ratings.
   ///
   /// [Gravatar documentation]: https://gravatar.com/site/implement/images/#rating
   pub rating: Option<String>,
+
+  /// Whether to request the original, un-resized upload from mirrors that
+  /// support it, defaults to `false`.
+  pub request_original: bool,
+
+  /// Whether to add `@2x` after the hash for static mirrors serving retina
+  /// images, defaults to `false`.
+  pub retina_suffix: bool,
+
+  /// The scheme for generated URLs, defaults to `https`.
+  pub scheme: String,
+
+  /// The largest image size that may be emitted, defaults to `None`. Larger
+  /// image sizes are clamped down to it.
+  pub size_cap: Option<i32>,
+
+  /// Whether mirrors should strip metadata like EXIF from images, defaults
+  /// to `false`.
+  pub strip_metadata: bool,
+
+  /// A salt added in front of emails before hashing, see
+  /// [`Generator::set_tenant_salt`], defaults to `None`.
+  pub tenant_salt: Option<String>,
+
+  /// A provider for a `token=<token>` query parameter that's called for every
+  /// generated URL, defaults to `None`.
+  pub token_provider: Option<TokenProvider>,
+
+  /// A template for generated URLs, defaults to `None`. See
+  /// [`Generator::set_url_template`] for the placeholders.
+  pub url_template: Option<String>,
+}
+
+/// The smart-crop modes for mirrors that support `crop=`, see
+/// [`Generator::set_crop_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CropMode {
+  /// Crops around the center of the image, `crop=center`.
+  Center,
+
+  /// Crops around detected faces, `crop=face`.
+  Face,
+
+  /// Crops around the most detailed region of the image, `crop=entropy`.
+  Entropy,
+}
+
+impl CropMode {
+  /// Returns the value used for this mode in the `crop=` parameter.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Center => "center",
+      Self::Face => "face",
+      Self::Entropy => "entropy",
+    }
+  }
+}
+
+impl std::str::FromStr for CropMode {
+  type Err = GeneratorError;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "center" => Ok(Self::Center),
+      "face" => Ok(Self::Face),
+      "entropy" => Ok(Self::Entropy),
+      _ => Err(GeneratorError::InvalidCropMode(value.to_string())),
+    }
+  }
+}
+
+/// How [`Generator::display_hash`] formats hashes, see
+/// [`Generator::set_hash_display`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HashDisplay {
+  /// Splits the hash into space-separated groups of this many characters,
+  /// defaults to `None`.
+  pub group_size: Option<usize>,
+
+  /// Whether the hash is uppercased, defaults to `false`.
+  pub uppercase: bool,
+}
+
+impl HashDisplay {
+  /// Creates a new HashDisplay.
+  pub fn new(group_size: Option<usize>, uppercase: bool) -> Self {
+    Self {
+      group_size,
+      uppercase,
+    }
+  }
+}
+
+/// The Gravatar ratings, from least to most explicit, see
+/// [`Generator::generate_by_rating`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rating {
+  /// Suitable for display on all websites with any audience type, `r=g`.
+  G,
+
+  /// May contain rude gestures or provocatively dressed individuals, `r=pg`.
+  Pg,
+
+  /// May contain harsh profanity, intense violence or nudity, `r=r`.
+  R,
+
+  /// May contain hardcore sexual imagery or extremely disturbing violence,
+  /// `r=x`.
+  X,
+}
+
+impl Rating {
+  /// All ratings, from least to most explicit.
+  pub const ALL: [Self; 4] = [Self::G, Self::Pg, Self::R, Self::X];
+
+  /// Returns the value used for this rating in the `r=` parameter.
+  pub fn as_str(&self) -> &'static str {
+    RATINGS[*self as usize]
+  }
+}
+
+/// The layout of the path in generated URLs, see
+/// [`Generator::set_path_style`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PathStyle {
+  /// The standard Gravatar layout, `/avatar/<hash>`.
+  #[default]
+  Flat,
+
+  /// A layout sharded by the first two pairs of hash characters, like
+  /// `/av/eb/ff/<hash>`, to spread objects across prefixes.
+  Sharded,
+
+  /// A layout with a bucket name instead of `avatar`, `/<bucket>/<hash>`.
+  /// The bucket may only contain unreserved URL characters, which are ASCII
+  /// letters, digits, `-`, `.`, `_` and `~`, as checked by
+  /// [`Generator::seal`].
+  Bucketed(String),
+}
+
+impl PathStyle {
+  /// Returns the path for a file in this layout, without a leading slash.
+  /// The shards come from the full hash, so they're the same regardless of
+  /// hash truncation or suffixes in the file name.
+  pub(crate) fn path(&self, full_hash: &str, file: &str) -> String {
+    match self {
+      Self::Flat => format!("avatar/{file}"),
+      Self::Sharded => {
+        let first = full_hash.get(..2).unwrap_or(full_hash);
+        let second = full_hash.get(2..4).unwrap_or_default();
+        format!("av/{first}/{second}/{file}")
+      }
+      Self::Bucketed(bucket) => format!("{bucket}/{file}"),
+    }
+  }
+}
+
+/// A function providing tokens for generated URLs, see
+/// [`Generator::set_token_provider`].
+#[derive(Clone)]
+pub struct TokenProvider(Arc<dyn Fn() -> String + Send + Sync>);
+
+impl TokenProvider {
+  /// Calls the provider and returns a new token.
+  pub fn token(&self) -> String {
+    (self.0)()
+  }
+}
+
+impl std::fmt::Debug for TokenProvider {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("TokenProvider")
+  }
 }
 
 impl Default for Generator {
   fn default() -> Self {
     Self {
       base_url: "www.gravatar.com".to_string(),
+      crawler_user_agents: DEFAULT_CRAWLER_USER_AGENTS
+        .map(ToString::to_string)
+        .to_vec(),
+      crop_mode: None,
+      csp_nonce: None,
       default_image: None,
+      default_image_size: None,
+      density_descriptors: vec![1.0, 2.0],
+      dimensions: None,
+      encoding_safe_chars: String::new(),
+      experiment: None,
+      extension_separator: ".".to_string(),
+      fallback_pool: vec![],
+      fragment: None,
       force_default: false,
+      force_jpeg: false,
+      force_requires_default: false,
+      hash_display: HashDisplay::default(),
+      hash_length: None,
+      hasher: Arc::new(Md5Hasher),
       image_size: None,
       include_file_extension: false,
+      lazy_loading: false,
+      local_fallback_dir: None,
+      param_prefix: String::new(),
+      path_style: PathStyle::Flat,
+      private: false,
       rating: None,
+      request_original: false,
+      retina_suffix: false,
+      scheme: "https".to_string(),
+      size_cap: None,
+      strip_metadata: false,
+      tenant_salt: None,
+      token_provider: None,
+      url_template: None,
     }
   }
 }
@@ -89,158 +443,1403 @@ impl Generator {
   ///
   /// [Gravatar hashing steps]: https://en.gravatar.com/site/implement/hash/
   pub fn hash_email(email: &str) -> String {
-    let hash = md5::compute(email.trim().to_lowercase());
+    let hash = md5::compute(normalize_email(email));
     format!("{hash:x}")
   }
 
-  /// Generates a new Gravatar image URL using the Generator's configuration.
+  /// Returns the email's hash formatted for display, for example on account
+  /// settings pages where users verify which avatar they're linked to. The
+  /// hash is the exact one used in generated URLs, including any truncation
+  /// from [`Generator::set_hash_length`], formatted according to
+  /// [`Generator::set_hash_display`].
   ///
-  /// See the top-level module documentation for examples.
-  pub fn generate(&self, email: &str) -> String {
-    let base_url = &self.base_url;
-    let hash = Self::hash_email(email);
-    let query_parameters = self.query_parameters();
-
-    let file_extension = if self.include_file_extension {
-      ".jpg"
+  /// ```rust
+  /// use gravatar_rs::{Generator, HashDisplay};
+  ///
+  /// let hash = Generator::default()
+  ///   .set_hash_display(HashDisplay::new(Some(8), true))
+  ///   .display_hash("helllo@holllo.cc");
+  ///
+  /// assert_eq!(hash, "EBFF9105 DCE4954B 1BDB57FD AB079FF3");
+  /// ```
+  pub fn display_hash(&self, email: &str) -> String {
+    let hash = self.hash(email);
+    let hash = self.url_hash(&hash);
+    let hash = if self.hash_display.uppercase {
+      hash.to_uppercase()
     } else {
-      ""
+      hash.to_string()
+    };
+
+    match self.hash_display.group_size {
+      Some(size) if size > 0 => hash
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(size)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" "),
+      _ => hash,
+    }
+  }
+
+  /// Returns the shortest valid Gravatar image URL for an email, using the
+  /// default host without any options or file extension. Useful as a
+  /// canonical form for comparing and storing avatar URLs.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(
+  ///   Generator::minimal_url("helllo@holllo.cc"),
+  ///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  /// );
+  /// ```
+  pub fn minimal_url(email: &str) -> String {
+    Self::default().generate(email)
+  }
+
+  /// Returns a stable name for the email's avatar, for correlating Resource
+  /// Timing entries without exposing the email. It's the generated URL
+  /// without any query parameters or fragment, so it only identifies the
+  /// avatar by its hash and doesn't leak tokens either. Match it against the
+  /// entry names with their query strings removed.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let name = Generator::default()
+  ///   .set_image_size(64)
+  ///   .resource_name("helllo@holllo.cc");
+  ///
+  /// assert_eq!(
+  ///   name,
+  ///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  /// );
+  /// ```
+  pub fn resource_name(&self, email: &str) -> String {
+    let generator = Self {
+      fragment: None,
+      ..self.clone()
+    };
+    generator.build_url_with_query(&self.base_url, &self.hash(email), "")
+  }
+
+  /// Returns the key of the email's avatar in a CDN bucket, like
+  /// `avatars/<size>/<hash>.jpg`. The size is the configured size, or
+  /// `<width>x<height>` for custom dimensions, falling back to Gravatar's
+  /// default of 80 pixels. The extension is only included when the
+  /// Generator adds one to URLs.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let key = Generator::default()
+  ///   .set_image_size(128)
+  ///   .set_include_file_extension(true)
+  ///   .cdn_object_key("helllo@holllo.cc");
+  ///
+  /// assert_eq!(key, "avatars/128/ebff9105dce4954b1bdb57fdab079ff3.jpg");
+  /// ```
+  pub fn cdn_object_key(&self, email: &str) -> String {
+    let size = match self.effective_dimensions() {
+      Some((width, height)) => format!("{width}x{height}"),
+      None => self.display_dimensions().0.to_string(),
     };
+    let hash = self.hash(email);
 
     format!(
-      "https://{base_url}/avatar/{hash}{file_extension}{query_parameters}"
+      "avatars/{size}/{}{}",
+      self.url_hash(&hash),
+      self.file_extension()
     )
   }
 
-  /// Returns all configurable options as a query parameter string.
-  pub fn query_parameters(&self) -> String {
-    fn encode<D: std::fmt::Display>(data: D) -> String {
-      urlencoding::encode(&data.to_string()).into_owned()
+  /// Returns the leading `bits` bits of the email's hash as hex, for
+  /// k-anonymity style lookups where only a prefix of the hash is shared.
+  ///
+  /// When `bits` isn't a multiple of 4 the last hex digit has its unused
+  /// bits set to zero, and `bits` is capped at the 128 bits of the hash.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert_eq!(Generator::hash_prefix("helllo@holllo.cc", 16), "ebff");
+  /// ```
+  pub fn hash_prefix(email: &str, bits: u32) -> String {
+    let bits = bits.min(128);
+    let hash = u128::from_be_bytes(md5::compute(normalize_email(email)).0);
+    let digits = bits.div_ceil(4);
+
+    if digits == 0 {
+      return String::new();
     }
 
-    let mut query_parameters = vec![];
+    let prefix = (hash >> (128 - bits)) << (digits * 4 - bits);
+    format!("{prefix:0width$x}", width = digits as usize)
+  }
 
-    if let Some(default_image) = &self.default_image {
-      query_parameters.push(format!("d={}", encode(default_image)));
+  /// Checks whether a default image is either one of the keywords Gravatar
+  /// supports or a syntactically valid absolute HTTP(S) URL.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// assert!(Generator::is_valid_default_image("identicon"));
+  /// assert!(Generator::is_valid_default_image("https://example.com/a.png"));
+  /// assert!(!Generator::is_valid_default_image("https://"));
+  /// ```
+  pub fn is_valid_default_image(value: &str) -> bool {
+    if DEFAULT_IMAGES.contains(&value) {
+      return true;
     }
 
-    if self.force_default {
-      query_parameters.push("f=y".to_string());
-    }
+    let Some(rest) = value
+      .strip_prefix("https://")
+      .or_else(|| value.strip_prefix("http://"))
+    else {
+      return false;
+    };
 
-    if let Some(image_size) = self.image_size {
-      query_parameters.push(format!("s={}", encode(image_size)));
-    }
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let valid_host = !host.is_empty()
+      && !host.starts_with(['.', '-', ':'])
+      && host
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || "-.:".contains(char));
 
-    if let Some(rating) = &self.rating {
-      query_parameters.push(format!("r={}", encode(rating)));
-    }
+    valid_host && !value.chars().any(|char| char.is_whitespace())
+  }
 
-    if query_parameters.is_empty() {
-      String::new()
+  /// Generates a new image URL for a request, using `https` when the request
+  /// itself is secure to avoid mixed content, and the Generator's scheme
+  /// otherwise.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default().set_scheme("http");
+  ///
+  /// assert!(generator
+  ///   .generate_for_request("helllo@holllo.cc", true)
+  ///   .starts_with("https://"));
+  /// ```
+  pub fn generate_for_request(&self, email: &str, is_secure: bool) -> String {
+    if is_secure && self.scheme != "https" {
+      self.clone().set_scheme("https").generate(email)
     } else {
-      format!("?{}", query_parameters.join("&"))
+      self.generate(email)
     }
   }
 
-  /// Configures the Generator to use a custom base URL for generated URLs.
+  /// Generates a new image URL for a request's user agent. Crawlers, detected
+  /// by the user agent containing one of the configured
+  /// [crawler substrings](Generator::set_crawler_user_agents), get a URL
+  /// with the default image forced so scrapers can't tell whether an email
+  /// has a Gravatar. Other user agents get the normal URL.
   ///
   /// ```rust
   /// use gravatar_rs::Generator;
   ///
-  /// // Use Libravatar instead of Gravatar.
-  /// Generator::default().set_base_url("cdn.libravatar.org");
+  /// let url = Generator::default().generate_for_user_agent(
+  ///   "helllo@holllo.cc",
+  ///   "Mozilla/5.0 (compatible; Googlebot/2.1)",
+  /// );
+  ///
+  /// assert!(url.ends_with("?d=mp&f=y"));
   /// ```
-  pub fn set_base_url(self, base_url: &str) -> Self {
-    Self {
-      base_url: base_url.to_string(),
-      ..self
+  pub fn generate_for_user_agent(
+    &self,
+    email: &str,
+    user_agent: &str,
+  ) -> String {
+    let user_agent = user_agent.to_lowercase();
+    let is_crawler = self
+      .crawler_user_agents
+      .iter()
+      .any(|crawler| user_agent.contains(&crawler.to_lowercase()));
+
+    if !is_crawler {
+      return self.generate(email);
+    }
+
+    let mut generator = self.clone().set_force_default(true);
+    if !generator.has_default_image() {
+      generator = generator.set_default_image("mp");
     }
+
+    generator.generate(email)
   }
 
-  /// Configures the Generator to include `d=<default image>` in the URL.
-  ///
-  /// See the [Gravatar documentation] for all the possible ways to use it.
+  /// Returns the recommended `Cache-Control` header value for proxies serving
+  /// the avatar of an email.
   ///
-  /// [Gravatar documentation]: https://gravatar.com/site/implement/images/#default-image
+  /// Private and salted Generators return `private, max-age=0` so shared
+  /// caches don't store the avatar, otherwise `public, max-age=86400` is
+  /// returned.
   ///
   /// ```rust
   /// use gravatar_rs::Generator;
   ///
-  /// // Use the "identicon" default image, a geometric pattern based on the
-  /// // email hash.
-  /// Generator::default().set_default_image("identicon");
+  /// let generator = Generator::default().set_private(true);
+  ///
+  /// assert_eq!(
+  ///   generator.cache_control_for("helllo@holllo.cc"),
+  ///   "private, max-age=0"
+  /// );
   /// ```
-  pub fn set_default_image(self, default_image: &str) -> Self {
-    Self {
-      default_image: Some(default_image.to_string()),
-      ..self
+  pub fn cache_control_for(&self, _email: &str) -> &'static str {
+    if self.private || self.tenant_salt.is_some() {
+      "private, max-age=0"
+    } else {
+      "public, max-age=86400"
     }
   }
 
-  /// When set to true, the Generator will always add `f=y` to the URL. Making
-  /// Gravatar always return the default image.
+  /// Returns a quoted `ETag` value for proxies serving the avatar of an
+  /// email, combining the email's hash with a short digest of the generated
+  /// URL. The value is stable across runs and only changes with options that
+  /// change the URL, while per-request values like the token, the fragment
+  /// and HTML-only options like the CSP nonce are left out.
   ///
   /// ```rust
   /// use gravatar_rs::Generator;
   ///
-  /// Generator::default().set_force_default(true);
+  /// let etag = Generator::default().etag_for("helllo@holllo.cc");
+  ///
+  /// assert!(etag.starts_with("\"ebff9105dce4954b1bdb57fdab079ff3-"));
   /// ```
-  pub fn set_force_default(self, force_default: bool) -> Self {
-    Self {
-      force_default,
-      ..self
+  pub fn etag_for(&self, email: &str) -> String {
+    format!("\"{}\"", self.cache_key(email))
+  }
+
+  /// Returns a key combining the email's hash with a short digest of the
+  /// generated URL, used by [`Generator::etag_for`].
+  pub(crate) fn cache_key(&self, email: &str) -> String {
+    let url = Self {
+      fragment: None,
+      token_provider: None,
+      ..self.clone()
     }
+    .generate(email);
+    let digest = format!("{:x}", md5::compute(url));
+    format!("{}-{}", self.hash(email), &digest[..8])
   }
 
-  /// Configures the Generator to include a `s=<image size>` in the URL.
+  /// Generates a new Gravatar image URL using the Generator's configuration.
+  ///
+  /// See the top-level module documentation for examples.
+  pub fn generate(&self, email: &str) -> String {
+    self.build_url(&self.base_url, &self.hash(email))
+  }
+
+  /// Generates a URL with the avatar's content hash added as `ic=`, for
+  /// content-addressed caching. Because the URL changes whenever the content
+  /// does, it's safe to cache forever. The content hash comes from the
+  /// caller, for example from a previous fetch of the image.
   ///
   /// ```rust
   /// use gravatar_rs::Generator;
   ///
-  /// // Get 128px images instead of the default 80px.
-  /// Generator::default().set_image_size(128);
+  /// let url = Generator::default()
+  ///   .set_image_size(64)
+  ///   .immutable_url("helllo@holllo.cc", "5d41402a");
+  ///
+  /// assert!(url.ends_with("?s=64&ic=5d41402a"));
   /// ```
-  pub fn set_image_size(self, image_size: i32) -> Self {
-    Self {
-      image_size: Some(image_size),
-      ..self
-    }
+  pub fn immutable_url(&self, email: &str, content_hash: &str) -> String {
+    self.generate_with_parameter(email, "ic", content_hash)
   }
 
-  /// Configures the Generator to add `.jpg` to the end of the hash.
+  /// Generates a URL for embedding the email's avatar in a newsletter, with
+  /// `c=<campaign>` added for correlating opens with a campaign. Because
+  /// email clients need absolute URLs that look like images, the URL always
+  /// uses `https` and the `.jpg` extension.
   ///
   /// ```rust
   /// use gravatar_rs::Generator;
   ///
-  /// Generator::default().set_include_file_extension(true);
+  /// let url = Generator::default()
+  ///   .set_scheme("http")
+  ///   .newsletter_avatar("helllo@holllo.cc", "spring 2024");
+  ///
+  /// assert_eq!(
+  ///   url,
+  ///   "https://www.gravatar.com/avatar/\
+  ///    ebff9105dce4954b1bdb57fdab079ff3.jpg?c=spring%202024"
+  /// );
   /// ```
-  pub fn set_include_file_extension(
-    self,
-    include_file_extension: bool,
-  ) -> Self {
-    Self {
-      include_file_extension,
-      ..self
-    }
+  pub fn newsletter_avatar(&self, email: &str, campaign: &str) -> String {
+    self
+      .clone()
+      .set_scheme("https")
+      .set_include_file_extension(true)
+      .generate_with_parameter(email, "c", campaign)
   }
 
-  /// Configures the Generator to include `r=<rating>` in the URL.
-  ///
-  /// See the [Gravatar documentation] for all the possible ratings.
-  ///
-  /// [Gravatar documentation]: https://gravatar.com/site/implement/images/#rating
+  /// Generates a URL like [`Generator::generate`] with one more query
+  /// parameter added after the configured ones.
+  fn generate_with_parameter(
+    &self,
+    email: &str,
+    key: &str,
+    value: &str,
+  ) -> String {
+    let hash = self.hash(email);
+    let query_parameters = self.with_fallback_pool(&hash).query_parameters();
+    let separator = if query_parameters.is_empty() {
+      '?'
+    } else {
+      '&'
+    };
+    let parameter =
+      format!("{}{key}={}", self.param_prefix, urlencoding::encode(value));
+
+    let query_parameters = format!("{query_parameters}{separator}{parameter}");
+    self.build_url_with_query(&self.base_url, &hash, &query_parameters)
+  }
+
+  /// Generates a URL for a square, face-cropped thumbnail of `size` pixels,
+  /// adding `crop=face` and `s=<size>` for mirrors that support smart
+  /// cropping. The size is clamped to the range Gravatar supports and
+  /// replaces any configured size or dimensions.
   ///
   /// ```rust
   /// use gravatar_rs::Generator;
   ///
-  /// // Allow G and PG rated images.
-  /// Generator::default().set_rating("pg");
+  /// let url = Generator::default().face_thumbnail_url("helllo@holllo.cc", 48);
+  ///
+  /// assert!(url.ends_with("?s=48&crop=face"));
   /// ```
-  pub fn set_rating(self, rating: &str) -> Self {
+  pub fn face_thumbnail_url(&self, email: &str, size: u32) -> String {
+    let size = size.clamp(MIN_IMAGE_SIZE as u32, MAX_IMAGE_SIZE as u32);
+
     Self {
-      rating: Some(rating.to_string()),
-      ..self
+      dimensions: None,
+      request_original: false,
+      ..self.clone()
+    }
+    .set_crop_mode(CropMode::Face)
+    .set_image_size(size as i32)
+    .generate(email)
+  }
+
+  /// Generates a URL for every [`Rating`], so clients can pick one based on
+  /// the viewer's audience settings. The email is only hashed once.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, Rating};
+  ///
+  /// let urls = Generator::default().generate_by_rating("helllo@holllo.cc");
+  ///
+  /// assert_eq!(urls.len(), 4);
+  /// assert_eq!(urls[1].0, Rating::Pg);
+  /// assert!(urls[1].1.ends_with("?r=pg"));
+  /// ```
+  pub fn generate_by_rating(&self, email: &str) -> Vec<(Rating, String)> {
+    let hash = self.hash(email);
+
+    Rating::ALL
+      .into_iter()
+      .map(|rating| {
+        let generator = self.clone().set_rating(rating.as_str());
+        (rating, generator.build_url(&self.base_url, &hash))
+      })
+      .collect()
+  }
+
+  /// Hashes an email with the Generator's [`Hasher`].
+  pub(crate) fn hash(&self, email: &str) -> String {
+    self.hash_normalized(&normalize_email(email))
+  }
+
+  /// Hashes an already normalized identity with the Generator's [`Hasher`].
+  pub(crate) fn hash_normalized(&self, identity: &str) -> String {
+    match &self.tenant_salt {
+      Some(salt) => self
+        .hasher
+        .hash(&format!("{}:{salt}{identity}", salt.len())),
+      None => self.hasher.hash(identity),
+    }
+  }
+
+  /// Returns the part of a hash used in URLs, which is the full hash unless
+  /// [`Generator::set_hash_length`] configured a shorter one.
+  pub(crate) fn url_hash<'a>(&self, hash: &'a str) -> &'a str {
+    match self.hash_length {
+      Some(length) => hash.get(..length).unwrap_or(hash),
+      None => hash,
     }
   }
+
+  /// Returns the Generator with its default image set to the hash's pick
+  /// from the fallback pool and the pool cleared, or the Generator itself
+  /// when the pool is empty.
+  pub(crate) fn with_fallback_pool(&self, hash: &str) -> Cow<'_, Self> {
+    if self.fallback_pool.is_empty() {
+      return Cow::Borrowed(self);
+    }
+
+    let index = pick_index(hash, self.fallback_pool.len());
+    Cow::Owned(Self {
+      default_image: Some(self.fallback_pool[index].clone()),
+      fallback_pool: vec![],
+      ..self.clone()
+    })
+  }
+
+  /// Returns the file extension added after the hash, if any.
+  pub(crate) fn file_extension(&self) -> String {
+    if self.include_file_extension || self.force_jpeg {
+      format!("{}jpg", self.extension_separator)
+    } else {
+      String::new()
+    }
+  }
+
+  /// Builds an image URL for an already computed hash, using a specific base
+  /// URL instead of the Generator's own.
+  pub(crate) fn build_url(&self, base_url: &str, hash: &str) -> String {
+    let query_parameters = self.with_fallback_pool(hash).query_parameters();
+    self.build_url_with_query(base_url, hash, &query_parameters)
+  }
+
+  /// Builds an image URL like [`Generator::build_url`], with already built
+  /// query parameters.
+  pub(crate) fn build_url_with_query(
+    &self,
+    base_url: &str,
+    hash: &str,
+    query_parameters: &str,
+  ) -> String {
+    let file_extension = self.file_extension();
+
+    let fragment = match &self.fragment {
+      Some(fragment) => format!("#{}", encode_fragment(fragment)),
+      None => String::new(),
+    };
+
+    let full_hash = hash;
+    let retina_suffix = if self.retina_suffix { "@2x" } else { "" };
+    let hash = format!("{}{retina_suffix}", self.url_hash(full_hash));
+
+    if let Some(template) = &self.url_template {
+      let url = template
+        .replace("{scheme}", &self.scheme)
+        .replace("{host}", base_url)
+        .replace("{hash}", &hash)
+        .replace("{ext}", &file_extension)
+        .replace("{query}", query_parameters);
+      return format!("{url}{fragment}");
+    }
+
+    let path = self.path_style.path(full_hash, &hash);
+    let url = format!("{}://{base_url}/{path}{file_extension}", self.scheme);
+    format!("{url}{query_parameters}{fragment}")
+  }
+
+  /// Generates a new image URL like [`Generator::generate`], but returns an
+  /// error when [`Generator::set_force_requires_default`] is enabled and
+  /// `force_default` is set without a default image.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, GeneratorError};
+  ///
+  /// let generator = Generator::default()
+  ///   .set_force_default(true)
+  ///   .set_force_requires_default(true);
+  ///
+  /// assert_eq!(
+  ///   generator.try_generate("helllo@holllo.cc"),
+  ///   Err(GeneratorError::ForceDefaultWithoutDefaultImage)
+  /// );
+  /// ```
+  pub fn try_generate(&self, email: &str) -> Result<String, GeneratorError> {
+    if self.has_force_default_conflict() {
+      return Err(GeneratorError::ForceDefaultWithoutDefaultImage);
+    }
+
+    Ok(self.generate(email))
+  }
+
+  /// Returns human-readable warnings about option combinations that are
+  /// valid but probably not what was intended.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default().set_force_default(true);
+  ///
+  /// assert_eq!(generator.warnings().len(), 1);
+  /// ```
+  pub fn warnings(&self) -> Vec<String> {
+    let mut warnings = vec![];
+
+    if self.force_default && !self.has_default_image() {
+      let consequence = if self.force_requires_default {
+        "so `f=y` is not emitted"
+      } else {
+        "so Gravatar will force its own default image"
+      };
+
+      warnings.push(format!(
+        "force_default is set without a default image, {consequence}"
+      ));
+    }
+
+    if self.default_image.is_some() && !self.fallback_pool.is_empty() {
+      warnings.push(
+        "default_image is set together with a fallback pool, so it's never \
+         used as the pool takes precedence"
+          .to_string(),
+      );
+    }
+
+    warnings
+  }
+
+  /// Returns whether a default image is set, either directly or through a
+  /// non-empty fallback pool.
+  pub(crate) fn has_default_image(&self) -> bool {
+    self.default_image.is_some() || !self.fallback_pool.is_empty()
+  }
+
+  /// Returns whether `f=y` should be withheld because `force_default` is set
+  /// without a default image while one is required.
+  pub(crate) fn has_force_default_conflict(&self) -> bool {
+    self.force_requires_default
+      && self.force_default
+      && !self.has_default_image()
+  }
+
+  /// Returns the image size as it will be emitted, clamped to the size cap.
+  pub(crate) fn effective_image_size(&self) -> Option<i32> {
+    let image_size = self.image_size?;
+    Some(self.size_cap.map_or(image_size, |cap| image_size.min(cap)))
+  }
+
+  /// Returns the width and height as they will be emitted, scaled down to
+  /// fit the size cap while keeping the aspect ratio.
+  pub(crate) fn effective_dimensions(&self) -> Option<(u32, u32)> {
+    let (width, height) = self.dimensions?;
+    let Some(cap) = self.size_cap else {
+      return Some((width, height));
+    };
+
+    let cap = cap.max(0) as u32;
+    let largest = width.max(height);
+    if largest <= cap {
+      return Some((width, height));
+    }
+
+    let scale = |side: u32| {
+      ((u64::from(side) * u64::from(cap) / u64::from(largest)) as u32).max(1)
+    };
+    Some((scale(width), scale(height)))
+  }
+
+  /// Returns whether `f=y` will be emitted.
+  pub(crate) fn effective_force_default(&self) -> bool {
+    self.force_default && !self.has_force_default_conflict()
+  }
+
+  /// Returns the `s=` image size as it will be emitted, which is `None` when
+  /// the original image or dimensions are requested instead.
+  pub(crate) fn effective_square_size(&self) -> Option<i32> {
+    if self.request_original || self.dimensions.is_some() {
+      return None;
+    }
+
+    self.effective_image_size()
+  }
+
+  /// Returns the default image as it will be emitted, before encoding. This
+  /// is `None` with a non-empty fallback pool, as the pool's pick depends on
+  /// the email.
+  pub(crate) fn effective_default_image(&self) -> Option<String> {
+    if !self.fallback_pool.is_empty() {
+      return None;
+    }
+
+    let default_image = self.default_image.clone()?;
+    let is_url = default_image.starts_with("https://")
+      || default_image.starts_with("http://");
+
+    match self.default_image_size {
+      Some(size) if is_url => {
+        let separator = if default_image.contains('?') {
+          '&'
+        } else {
+          '?'
+        };
+        Some(format!("{default_image}{separator}size={size}"))
+      }
+      _ => Some(default_image),
+    }
+  }
+
+  /// Returns all configurable options as a query parameter string.
+  ///
+  /// With a non-empty [fallback pool](Generator::set_fallback_pool) the
+  /// default image depends on the email, so `d=` is left out and
+  /// [`Generator::generate`] adds the email's pick instead.
+  pub fn query_parameters(&self) -> String {
+    // This also encodes `+` as `%2B`, so it's never decoded as a space.
+    fn encode<D: std::fmt::Display>(data: D) -> String {
+      urlencoding::encode(&data.to_string()).into_owned()
+    }
+
+    let mut query_parameters = vec![];
+
+    if let Some(default_image) = self.effective_default_image() {
+      let default_image = default_image
+        .split_inclusive(|char| self.encoding_safe_chars.contains(char))
+        .map(|part| match part.char_indices().last() {
+          Some((index, char)) if self.encoding_safe_chars.contains(char) => {
+            format!("{}{char}", encode(&part[..index]))
+          }
+          _ => encode(part),
+        })
+        .collect::<String>();
+      query_parameters.push(format!("d={default_image}"));
+    }
+
+    if self.effective_force_default() {
+      query_parameters.push("f=y".to_string());
+    }
+
+    if self.request_original {
+      query_parameters.push("original=1".to_string());
+    } else if let Some((width, height)) = self.effective_dimensions() {
+      query_parameters.push(format!("w={}", encode(width)));
+      query_parameters.push(format!("h={}", encode(height)));
+    } else if let Some(image_size) = self.effective_square_size() {
+      query_parameters.push(format!("s={}", encode(image_size)));
+    }
+
+    if let Some(crop_mode) = self.crop_mode {
+      query_parameters.push(format!("crop={}", crop_mode.as_str()));
+    }
+
+    if self.strip_metadata {
+      query_parameters.push("strip=1".to_string());
+    }
+
+    if let Some(rating) = &self.rating {
+      query_parameters.push(format!("r={}", encode(rating)));
+    }
+
+    if let Some(experiment) = &self.experiment {
+      query_parameters.push(format!("exp={}", encode(experiment)));
+    }
+
+    if let Some(token_provider) = &self.token_provider {
+      query_parameters
+        .push(format!("token={}", encode(token_provider.token())));
+    }
+
+    if query_parameters.is_empty() {
+      return String::new();
+    }
+
+    let prefix = &self.param_prefix;
+    let query_parameters = query_parameters
+      .into_iter()
+      .map(|parameter| format!("{prefix}{parameter}"))
+      .collect::<Vec<_>>();
+
+    format!("?{}", query_parameters.join("&"))
+  }
+
+  /// Configures the Generator to use a custom base URL for generated URLs.
+  ///
+  /// The host is lowercased so differently cased hosts don't fragment caches,
+  /// while the case of any path after it is kept.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Use Libravatar instead of Gravatar.
+  /// Generator::default().set_base_url("cdn.libravatar.org");
+  /// ```
+  pub fn set_base_url(self, base_url: &str) -> Self {
+    let base_url = match base_url.split_once('/') {
+      Some((host, path)) => format!("{}/{path}", host.to_lowercase()),
+      None => base_url.to_lowercase(),
+    };
+
+    Self { base_url, ..self }
+  }
+
+  /// Configures the substrings that mark a user agent as a crawler for
+  /// [`Generator::generate_for_user_agent`]. Matching is case-insensitive.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_crawler_user_agents(vec!["archiver".to_string()]);
+  /// ```
+  pub fn set_crawler_user_agents(
+    self,
+    crawler_user_agents: Vec<String>,
+  ) -> Self {
+    Self {
+      crawler_user_agents,
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `crop=<mode>` in the URL, for mirrors
+  /// that support smart cropping. Gravatar itself ignores it.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{CropMode, Generator};
+  ///
+  /// Generator::default().set_crop_mode(CropMode::Face);
+  /// ```
+  pub fn set_crop_mode(self, crop_mode: CropMode) -> Self {
+    Self {
+      crop_mode: Some(crop_mode),
+      ..self
+    }
+  }
+
+  /// Configures a Content Security Policy nonce that HTML helpers like
+  /// [`Generator::generate_img_tag`] add as a `nonce` attribute, for sites
+  /// with a strict CSP.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_csp_nonce(Some("r4nd0m"));
+  /// ```
+  pub fn set_csp_nonce(self, csp_nonce: Option<&str>) -> Self {
+    Self {
+      csp_nonce: csp_nonce.map(ToString::to_string),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `d=<default image>` in the URL.
+  ///
+  /// See the [Gravatar documentation] for all the possible ways to use it.
+  ///
+  /// [Gravatar documentation]: https://gravatar.com/site/implement/images/#default-image
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Use the "identicon" default image, a geometric pattern based on the
+  /// // email hash.
+  /// Generator::default().set_default_image("identicon");
+  /// ```
+  pub fn set_default_image(self, default_image: &str) -> Self {
+    Self {
+      default_image: Some(default_image.to_string()),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to add `size=<size>` to the default image when
+  /// it's a URL, for custom default images that support their own sizing.
+  /// The size is added before the default image is encoded, default image
+  /// keywords like `identicon` are left untouched.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Uses `https://example.com/default.png?size=80` as the default image.
+  /// Generator::default()
+  ///   .set_default_image("https://example.com/default.png")
+  ///   .set_default_image_size(80);
+  /// ```
+  pub fn set_default_image_size(self, default_image_size: u32) -> Self {
+    Self {
+      default_image_size: Some(default_image_size),
+      ..self
+    }
+  }
+
+  /// Configures the pixel densities [`Generator::generate_srcset`] creates
+  /// entries for.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_density_descriptors(vec![1.0, 1.5, 2.0, 3.0]);
+  /// ```
+  pub fn set_density_descriptors(self, density_descriptors: Vec<f32>) -> Self {
+    Self {
+      density_descriptors,
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `w=<width>&h=<height>` in the URL
+  /// instead of `s=<image size>`, for mirrors that support non-square images.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_dimensions(160, 90);
+  /// ```
+  pub fn set_dimensions(self, width: u32, height: u32) -> Self {
+    Self {
+      dimensions: Some((width, height)),
+      ..self
+    }
+  }
+
+  /// Configures extra characters to leave unencoded in the default image,
+  /// for example `:/` so a fallback URL stays readable.
+  ///
+  /// Leaving characters unencoded is only safe when the image service still
+  /// parses the URL the same way. Never mark `&`, `#` or `+` as safe, as an
+  /// unencoded `&` or `#` ends the default image early and an unencoded `+`
+  /// is decoded as a space.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let url = Generator::default()
+  ///   .set_default_image("https://example.com/a.png")
+  ///   .set_encoding_safe_chars(":/")
+  ///   .generate("helllo@holllo.cc");
+  ///
+  /// assert!(url.ends_with("?d=https://example.com/a.png"));
+  /// ```
+  pub fn set_encoding_safe_chars(self, encoding_safe_chars: &str) -> Self {
+    Self {
+      encoding_safe_chars: encoding_safe_chars.to_string(),
+      ..self
+    }
+  }
+
+  /// Configures the separator between the hash and the file extension, for
+  /// mirrors with naming schemes like `<hash>-jpg` or `<hash>_thumb.jpg`.
+  ///
+  /// The separator may only contain unreserved URL characters, which are
+  /// ASCII letters, digits, `-`, `.`, `_` and `~`, as checked by
+  /// [`Generator::seal`].
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let url = Generator::default()
+  ///   .set_include_file_extension(true)
+  ///   .set_extension_separator("_thumb.")
+  ///   .generate("helllo@holllo.cc");
+  ///
+  /// assert!(url.ends_with("ebff9105dce4954b1bdb57fdab079ff3_thumb.jpg"));
+  /// ```
+  pub fn set_extension_separator(self, extension_separator: &str) -> Self {
+    Self {
+      extension_separator: extension_separator.to_string(),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `exp=<experiment>` in the URL, so
+  /// analytics can segment avatar requests by A/B test or origin trial.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_experiment(Some("rounded-avatars"));
+  /// ```
+  pub fn set_experiment(self, experiment: Option<&str>) -> Self {
+    Self {
+      experiment: experiment.map(ToString::to_string),
+      ..self
+    }
+  }
+
+  /// Configures a pool of default image URLs. Instead of a single default
+  /// image, every email gets one picked from the pool based on its hash, so
+  /// fallbacks are varied but the same email always gets the same one. A
+  /// non-empty pool takes precedence over [`Generator::set_default_image`].
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_fallback_pool(vec![
+  ///   "https://example.com/cat.png".to_string(),
+  ///   "https://example.com/dog.png".to_string(),
+  /// ]);
+  /// ```
+  pub fn set_fallback_pool(self, fallback_pool: Vec<String>) -> Self {
+    Self {
+      fallback_pool,
+      ..self
+    }
+  }
+
+  /// When set to true, the Generator will always add `f=y` to the URL. Making
+  /// Gravatar always return the default image.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_force_default(true);
+  /// ```
+  pub fn set_force_default(self, force_default: bool) -> Self {
+    Self {
+      force_default,
+      ..self
+    }
+  }
+
+  /// When set to true, the Generator always adds `.jpg` to the end of the
+  /// hash, regardless of [`Generator::set_include_file_extension`]. Mirrors
+  /// that serve WebP by default use the extension to return JPEG instead.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_force_jpeg(true);
+  /// ```
+  pub fn set_force_jpeg(self, force_jpeg: bool) -> Self {
+    Self { force_jpeg, ..self }
+  }
+
+  /// When set to true, `f=y` is only emitted when a default image is also
+  /// set, and [`Generator::try_generate`] and [`Generator::seal`] return an
+  /// error for the conflicting combination instead.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_force_requires_default(true);
+  /// ```
+  pub fn set_force_requires_default(
+    self,
+    force_requires_default: bool,
+  ) -> Self {
+    Self {
+      force_requires_default,
+      ..self
+    }
+  }
+
+  /// Configures the Generator to append `#<fragment>` to the URL, after the
+  /// query parameters. Only characters that aren't allowed in a fragment are
+  /// percent-encoded.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_fragment(Some("/profile"));
+  /// ```
+  pub fn set_fragment(self, fragment: Option<&str>) -> Self {
+    Self {
+      fragment: fragment.map(ToString::to_string),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to use a custom [`Hasher`] for emails, for
+  /// example [`Sha256Hasher`] or a custom hash function for private mirrors.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, Sha256Hasher};
+  ///
+  /// Generator::default().set_hasher(Box::new(Sha256Hasher));
+  /// ```
+  pub fn set_hasher(self, hasher: Box<dyn Hasher>) -> Self {
+    Self {
+      hasher: Arc::from(hasher),
+      ..self
+    }
+  }
+
+  /// Configures how [`Generator::display_hash`] formats hashes.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, HashDisplay};
+  ///
+  /// // Uppercase the hash in groups of four characters.
+  /// Generator::default().set_hash_display(HashDisplay::new(Some(4), true));
+  /// ```
+  pub fn set_hash_display(self, hash_display: HashDisplay) -> Self {
+    Self {
+      hash_display,
+      ..self
+    }
+  }
+
+  /// Configures the Generator to truncate hashes in URLs to `hash_length`
+  /// characters, for legacy mirrors that use short hashes.
+  ///
+  /// This is non-standard, Gravatar itself only accepts full hashes. The
+  /// length has to be a non-zero even number no longer than the full hash,
+  /// which [`Generator::seal`] checks.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let url = Generator::default()
+  ///   .set_hash_length(Some(16))
+  ///   .generate("helllo@holllo.cc");
+  ///
+  /// assert_eq!(url, "https://www.gravatar.com/avatar/ebff9105dce4954b");
+  /// ```
+  pub fn set_hash_length(self, hash_length: Option<usize>) -> Self {
+    Self {
+      hash_length,
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include a `s=<image size>` in the URL.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Get 128px images instead of the default 80px.
+  /// Generator::default().set_image_size(128);
+  /// ```
+  pub fn set_image_size(self, image_size: i32) -> Self {
+    Self {
+      image_size: Some(image_size),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to add `.jpg` to the end of the hash.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_include_file_extension(true);
+  /// ```
+  pub fn set_include_file_extension(
+    self,
+    include_file_extension: bool,
+  ) -> Self {
+    Self {
+      include_file_extension,
+      ..self
+    }
+  }
+
+  /// Configures whether generated `<img>` elements, like the one from
+  /// [`Generator::generate_img_tag`], get `loading="lazy"` and
+  /// `decoding="async"` so avatar-heavy pages load faster.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_lazy_loading(true);
+  /// ```
+  pub fn set_lazy_loading(self, lazy_loading: bool) -> Self {
+    Self {
+      lazy_loading,
+      ..self
+    }
+  }
+
+  /// Configures a directory with locally cached avatars, named
+  /// `<hash>.jpg`, that [`Generator::url_or_local_file`] prefers over the
+  /// network URL. A relative directory is resolved against the current
+  /// directory when the URL is built.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_local_fallback_dir("/var/cache/avatars".into());
+  /// ```
+  pub fn set_local_fallback_dir(self, local_fallback_dir: PathBuf) -> Self {
+    Self {
+      local_fallback_dir: Some(local_fallback_dir),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to prefix all query parameter keys, for proxies
+  /// that namespace the parameters they forward.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Emit `av_s=128` instead of `s=128`.
+  /// Generator::default().set_param_prefix("av_").set_image_size(128);
+  /// ```
+  pub fn set_param_prefix(self, param_prefix: &str) -> Self {
+    Self {
+      param_prefix: param_prefix.to_string(),
+      ..self
+    }
+  }
+
+  /// Configures the layout of the path after the base URL, for mirrors backed
+  /// by object storage. See [`PathStyle`] for the possible layouts.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, PathStyle};
+  ///
+  /// Generator::default().set_path_style(PathStyle::Sharded);
+  /// ```
+  pub fn set_path_style(self, path_style: PathStyle) -> Self {
+    Self { path_style, ..self }
+  }
+
+  /// Configures whether generated URLs should be treated as private, see
+  /// [`Generator::cache_control_for`].
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_private(true);
+  /// ```
+  pub fn set_private(self, private: bool) -> Self {
+    Self { private, ..self }
+  }
+
+  /// When set to true, the Generator adds `@2x` after the hash and before the
+  /// file extension, like `<hash>@2x.jpg`, for static mirrors that serve
+  /// retina images as separate files.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default()
+  ///   .set_include_file_extension(true)
+  ///   .set_retina_suffix(true);
+  /// ```
+  pub fn set_retina_suffix(self, retina_suffix: bool) -> Self {
+    Self {
+      retina_suffix,
+      ..self
+    }
+  }
+
+  /// Configures the scheme for generated URLs, either `https` or `http`.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Use plain HTTP for a local mirror.
+  /// Generator::default()
+  ///   .set_base_url("localhost:8080")
+  ///   .set_scheme("http");
+  /// ```
+  pub fn set_scheme(self, scheme: &str) -> Self {
+    Self {
+      scheme: scheme.to_string(),
+      ..self
+    }
+  }
+
+  /// Configures the largest image size the Generator will emit, clamping any
+  /// larger image size down to it. Dimensions are scaled down to fit the cap
+  /// while keeping their aspect ratio. Useful for enforcing a per-tenant
+  /// limit regardless of the sizes requested elsewhere.
+  ///
+  /// The cap has to be within the range Gravatar supports, which
+  /// [`Generator::seal`] checks.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Emits `s=256` instead of `s=1024`.
+  /// Generator::default().set_size_cap(256).set_image_size(1024);
+  /// ```
+  pub fn set_size_cap(self, size_cap: i32) -> Self {
+    Self {
+      size_cap: Some(size_cap),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `strip=1` in the URL, asking
+  /// privacy-focused mirrors to strip metadata like EXIF from images.
+  /// Gravatar itself ignores it.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_strip_metadata(true);
+  /// ```
+  pub fn set_strip_metadata(self, strip_metadata: bool) -> Self {
+    Self {
+      strip_metadata,
+      ..self
+    }
+  }
+
+  /// Configures a salt that's added in front of emails before hashing, so the
+  /// same email results in different hashes for different tenants and
+  /// avatars can't be correlated between them.
+  ///
+  /// The hashed value is the salt's length in bytes, a `:`, the salt and the
+  /// normalized email, like `8:tenant-1helllo@holllo.cc`. The length prefix
+  /// makes sure different salt and email pairs never hash the same value.
+  ///
+  /// Salted hashes don't match any Gravatar account, so this only works with
+  /// a tenant-aware private mirror. Salted Generators are also treated as
+  /// private by [`Generator::cache_control_for`].
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default()
+  ///   .set_base_url("avatars.tenant.example")
+  ///   .set_tenant_salt("tenant-1");
+  /// ```
+  pub fn set_tenant_salt(self, tenant_salt: &str) -> Self {
+    Self {
+      tenant_salt: Some(tenant_salt.to_string()),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `token=<token>` in the URL, calling
+  /// the provider for every generated URL. Useful for private mirrors that
+  /// require rotating tokens against hotlinking.
+  ///
+  /// The token provider isn't included in [`Generator::to_bytes`].
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_token_provider(|| "secret".to_string());
+  /// ```
+  pub fn set_token_provider(
+    self,
+    token_provider: impl Fn() -> String + Send + Sync + 'static,
+  ) -> Self {
+    Self {
+      token_provider: Some(TokenProvider(Arc::new(token_provider))),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `r=<rating>` in the URL.
+  ///
+  /// See the [Gravatar documentation] for all the possible ratings.
+  ///
+  /// [Gravatar documentation]: https://gravatar.com/site/implement/images/#rating
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Allow G and PG rated images.
+  /// Generator::default().set_rating("pg");
+  /// ```
+  pub fn set_rating(self, rating: &str) -> Self {
+    Self {
+      rating: Some(rating.to_string()),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `r=<rating>` in the URL using the
+  /// legacy numeric ratings, where `0` is `g`, `1` is `pg`, `2` is `r` and `3`
+  /// is `x`.
+  ///
+  /// Numbers outside of that range are kept as-is and reported as an invalid
+  /// rating by [`Generator::seal`].
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// // Equivalent to `set_rating("pg")`.
+  /// Generator::default().set_rating_numeric(1);
+  /// ```
+  pub fn set_rating_numeric(self, rating: u8) -> Self {
+    let rating = match RATINGS.get(usize::from(rating)) {
+      Some(rating) => rating.to_string(),
+      None => rating.to_string(),
+    };
+
+    Self {
+      rating: Some(rating),
+      ..self
+    }
+  }
+
+  /// Configures the Generator to include `original=1` in the URL instead of
+  /// a size, asking mirrors that support it for the original, un-resized
+  /// upload. Gravatar itself ignores it.
+  ///
+  /// Any `s=`, `w=` and `h=` parameters are omitted, and [`Generator::seal`]
+  /// reports an explicit image size or dimensions as a conflict.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// Generator::default().set_request_original(true);
+  /// ```
+  pub fn set_request_original(self, request_original: bool) -> Self {
+    Self {
+      request_original,
+      ..self
+    }
+  }
+
+  /// Configures a template for generated URLs, for avatar services with
+  /// their own URL layout. The template must contain `{hash}`, and the
+  /// following placeholders are replaced:
+  ///
+  /// - `{scheme}`: the scheme, like `https`.
+  /// - `{host}`: the base URL.
+  /// - `{hash}`: the email hash, including the `@2x` retina suffix if set.
+  /// - `{ext}`: the file extension including the dot, or nothing.
+  /// - `{query}`: the query parameters including the `?`, or nothing.
+  ///
+  /// The fragment is always added after the filled in template. Generated
+  /// URLs can still be checked with [`Generator::assert_roundtrip`], which
+  /// matches them against the template.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let url = Generator::default()
+  ///   .set_base_url("avatars.example.com")
+  ///   .set_url_template("{scheme}://{host}/u/{hash}/image{ext}{query}")
+  ///   .generate("helllo@holllo.cc");
+  ///
+  /// assert_eq!(
+  ///   url,
+  ///   "https://avatars.example.com/u/ebff9105dce4954b1bdb57fdab079ff3/image"
+  /// );
+  /// ```
+  pub fn set_url_template(self, url_template: &str) -> Self {
+    Self {
+      url_template: Some(url_template.to_string()),
+      ..self
+    }
+  }
+}
+
+/// Normalizes an email according to the [Gravatar hashing steps].
+///
+/// [Gravatar hashing steps]: https://en.gravatar.com/site/implement/hash/
+pub(crate) fn normalize_email(email: &str) -> String {
+  email.trim().to_lowercase()
+}
+
+/// Picks an index below `count` from a hash, so the same hash always picks
+/// the same index while different hashes spread evenly.
+pub(crate) fn pick_index(hash: &str, count: usize) -> usize {
+  let digest = md5::compute(hash);
+  let mut bytes = [0; 8];
+  bytes.copy_from_slice(&digest[..8]);
+  (u64::from_be_bytes(bytes) % count as u64) as usize
+}
+
+/// Percent-encodes the characters of a fragment that aren't allowed in one
+/// according to [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-3.5).
+fn encode_fragment(fragment: &str) -> String {
+  let mut encoded = String::with_capacity(fragment.len());
+
+  for byte in fragment.bytes() {
+    if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/?".contains(&byte) {
+      encoded.push(byte as char);
+    } else {
+      encoded.push_str(&format!("%{byte:02X}"));
+    }
+  }
+
+  encoded
 }
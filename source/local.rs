@@ -0,0 +1,79 @@
+//! Falling back to locally cached avatar files.
+
+use std::{
+  ffi::OsStr,
+  path::{Component, Path, Prefix},
+};
+
+use crate::Generator;
+
+impl Generator {
+  /// Returns a `file://` URL to `<dir>/<hash>.jpg` when that file exists in
+  /// the directory configured with [`Generator::set_local_fallback_dir`],
+  /// otherwise the network URL from [`Generator::generate`]. The file's path
+  /// is made absolute with [`std::fs::canonicalize`], so relative
+  /// directories are resolved against the current directory.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default();
+  ///
+  /// assert_eq!(
+  ///   generator.url_or_local_file("helllo@holllo.cc"),
+  ///   generator.generate("helllo@holllo.cc")
+  /// );
+  /// ```
+  pub fn url_or_local_file(&self, email: &str) -> String {
+    let Some(dir) = &self.local_fallback_dir else {
+      return self.generate(email);
+    };
+
+    let path = dir.join(format!("{}.jpg", self.hash(email)));
+    if !path.is_file() {
+      return self.generate(email);
+    }
+
+    std::fs::canonicalize(path)
+      .ok()
+      .and_then(|path| file_url(&path))
+      .unwrap_or_else(|| self.generate(email))
+  }
+}
+
+/// Builds a `file://` URL for an absolute path, with a drive letter like
+/// `file:///C:/` or a host like `file://server/share/` for Windows paths.
+/// Returns `None` for paths that can't be represented.
+fn file_url(path: &Path) -> Option<String> {
+  let mut host = String::new();
+  let mut url_path = String::new();
+
+  for component in path.components() {
+    match component {
+      Component::Prefix(prefix) => match prefix.kind() {
+        Prefix::Disk(disk) | Prefix::VerbatimDisk(disk) => {
+          url_path.push_str(&format!("/{}:", disk as char));
+        }
+        Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+          host = encode_segment(server);
+          url_path.push('/');
+          url_path.push_str(&encode_segment(share));
+        }
+        _ => return None,
+      },
+      Component::RootDir => {}
+      Component::Normal(segment) => {
+        url_path.push('/');
+        url_path.push_str(&encode_segment(segment));
+      }
+      Component::CurDir | Component::ParentDir => return None,
+    }
+  }
+
+  Some(format!("file://{host}{url_path}"))
+}
+
+/// Percent-encodes a single path segment.
+fn encode_segment(segment: &OsStr) -> String {
+  urlencoding::encode_binary(segment.as_encoded_bytes()).into_owned()
+}
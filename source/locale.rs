@@ -0,0 +1,92 @@
+//! Localized default images based on `Accept-Language` headers.
+
+use crate::Generator;
+
+impl Generator {
+  /// Generates a new image URL with the preferred language from an
+  /// `Accept-Language` header added to the default image as
+  /// `locale=<language>`, for default image services that localize their
+  /// images.
+  ///
+  /// The locale is only added when the default image is a URL and the header
+  /// contains at least one valid language. Malformed entries are ignored.
+  /// With a [fallback pool](Generator::set_fallback_pool) the locale is added
+  /// to the email's pick from the pool.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let url = Generator::default()
+  ///   .set_default_image("https://example.com/default.png")
+  ///   .generate_localized_default("helllo@holllo.cc", "en-US,en;q=0.9");
+  ///
+  /// assert!(url.ends_with("default.png%3Flocale%3Den-US"));
+  /// ```
+  pub fn generate_localized_default(
+    &self,
+    email: &str,
+    accept_language: &str,
+  ) -> String {
+    let generator = self.with_fallback_pool(&self.hash(email));
+    let Some(default_image) = &generator.default_image else {
+      return self.generate(email);
+    };
+
+    let is_url = default_image.starts_with("https://")
+      || default_image.starts_with("http://");
+
+    match preferred_language(accept_language) {
+      Some(language) if is_url => {
+        let separator = if default_image.contains('?') {
+          '&'
+        } else {
+          '?'
+        };
+        let default_image =
+          format!("{default_image}{separator}locale={language}");
+        generator
+          .into_owned()
+          .set_default_image(&default_image)
+          .generate(email)
+      }
+      _ => self.generate(email),
+    }
+  }
+}
+
+/// Returns the language with the highest quality from an `Accept-Language`
+/// header, preferring earlier languages when qualities are equal.
+fn preferred_language(accept_language: &str) -> Option<&str> {
+  let mut preferred: Option<(&str, f32)> = None;
+
+  for entry in accept_language.split(',') {
+    let mut parts = entry.split(';').map(str::trim);
+    let language = parts.next().unwrap_or_default();
+
+    let valid_language = !language.is_empty()
+      && language.split('-').all(|subtag| {
+        (1..=8).contains(&subtag.len())
+          && subtag.chars().all(|char| char.is_ascii_alphanumeric())
+      });
+    if !valid_language {
+      continue;
+    }
+
+    let mut quality = 1.0;
+    for parameter in parts {
+      if let Some(value) = parameter.strip_prefix("q=") {
+        quality = value.parse().unwrap_or(-1.0);
+      }
+    }
+
+    if quality <= 0.0 || quality > 1.0 {
+      continue;
+    }
+
+    if preferred.is_none_or(|(_, best)| quality > best) {
+      preferred = Some((language, quality));
+    }
+  }
+
+  preferred.map(|(language, _)| language)
+}
@@ -0,0 +1,36 @@
+//! Generating URLs for a list of emails stored in a file.
+
+use std::path::Path;
+
+use crate::{Error, Generator};
+
+impl Generator {
+  /// Reads newline-separated emails from a file and returns every email with
+  /// its generated URL, skipping blank lines.
+  ///
+  /// ```rust,no_run
+  /// use gravatar_rs::Generator;
+  ///
+  /// let manifest = Generator::default().generate_manifest("emails.txt")?;
+  ///
+  /// for (email, url) in manifest {
+  ///   println!("{email}: {url}");
+  /// }
+  /// # Ok::<(), gravatar_rs::Error>(())
+  /// ```
+  pub fn generate_manifest<P: AsRef<Path>>(
+    &self,
+    path: P,
+  ) -> Result<Vec<(String, String)>, Error> {
+    let contents = std::fs::read_to_string(path)?;
+
+    let manifest = contents
+      .lines()
+      .map(str::trim)
+      .filter(|email| !email.is_empty())
+      .map(|email| (email.to_string(), self.generate(email)))
+      .collect();
+
+    Ok(manifest)
+  }
+}
@@ -0,0 +1,62 @@
+//! Timing the steps of URL generation, see
+//! [`Generator::generate_with_metrics`].
+
+use std::time::{Duration, Instant};
+
+use crate::Generator;
+
+/// Measurements of a single URL generation, see
+/// [`Generator::generate_with_metrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GenerateMetrics {
+  /// The time spent normalizing and hashing the email.
+  pub hash_duration: Duration,
+
+  /// The time spent building the query parameters.
+  pub query_duration: Duration,
+
+  /// The time spent generating the whole URL.
+  pub total_duration: Duration,
+
+  /// The length of the generated URL in bytes.
+  pub url_length: usize,
+}
+
+impl Generator {
+  /// Generates a URL like [`Generator::generate`] and measures how long each
+  /// step took, for profiling generation in performance-sensitive code.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default();
+  /// let (url, metrics) = generator.generate_with_metrics("helllo@holllo.cc");
+  ///
+  /// assert_eq!(url, generator.generate("helllo@holllo.cc"));
+  /// assert_eq!(metrics.url_length, url.len());
+  /// ```
+  pub fn generate_with_metrics(
+    &self,
+    email: &str,
+  ) -> (String, GenerateMetrics) {
+    let start = Instant::now();
+    let hash = self.hash(email);
+    let hash_duration = start.elapsed();
+
+    let query_start = Instant::now();
+    let query_parameters = self.with_fallback_pool(&hash).query_parameters();
+    let query_duration = query_start.elapsed();
+
+    let url =
+      self.build_url_with_query(&self.base_url, &hash, &query_parameters);
+    let metrics = GenerateMetrics {
+      hash_duration,
+      query_duration,
+      total_duration: start.elapsed(),
+      url_length: url.len(),
+    };
+
+    (url, metrics)
+  }
+}
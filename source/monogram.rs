@@ -0,0 +1,76 @@
+//! Monogram placeholders built from a person's initials.
+
+use crate::{
+  html::escape_html, normalize_email, Generator, MAX_IMAGE_SIZE, MIN_IMAGE_SIZE,
+};
+
+impl Generator {
+  /// Generates a URL for the email's avatar that respects a Do-Not-Track
+  /// preference. When `dnt` is true, a local `data:` URL with a monogram of
+  /// the email's initials is returned instead, so no third-party request is
+  /// made. Otherwise the normal URL is returned.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default();
+  ///
+  /// assert!(generator
+  ///   .generate_respecting_dnt("helllo@holllo.cc", true)
+  ///   .starts_with("data:image/svg+xml,"));
+  /// assert_eq!(
+  ///   generator.generate_respecting_dnt("helllo@holllo.cc", false),
+  ///   generator.generate("helllo@holllo.cc")
+  /// );
+  /// ```
+  pub fn generate_respecting_dnt(&self, email: &str, dnt: bool) -> String {
+    if !dnt {
+      return self.generate(email);
+    }
+
+    let email = normalize_email(email);
+    let local_part = email.split('@').next().unwrap_or_default();
+    let name = local_part.replace(['.', '_', '-', '+'], " ");
+    let (size, _) = self.display_dimensions();
+    let size = size.clamp(MIN_IMAGE_SIZE.into(), MAX_IMAGE_SIZE.into());
+    let svg = monogram_svg(&name, Self::avatar_color(&email), size as u32);
+
+    format!("data:image/svg+xml,{}", urlencoding::encode(&svg))
+  }
+}
+
+/// Returns up to two uppercase initials from the first and last words of
+/// `name`, or `?` when `name` has no words.
+pub(crate) fn initials(name: &str) -> String {
+  let mut words = name.split_whitespace();
+  let Some(first) = words.next() else {
+    return "?".to_string();
+  };
+
+  std::iter::once(first)
+    .chain(words.last())
+    .filter_map(|word| word.chars().next())
+    .flat_map(char::to_uppercase)
+    .collect()
+}
+
+/// Builds a square SVG of `size` pixels with the initials of `name` centered
+/// on a background of `color`.
+pub(crate) fn monogram_svg(
+  name: &str,
+  color: (u8, u8, u8),
+  size: u32,
+) -> String {
+  let (red, green, blue) = color;
+  let initials = escape_html(&initials(name));
+  let font_size = size / 2;
+
+  format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" \
+     height=\"{size}\" viewBox=\"0 0 {size} {size}\">\
+     <rect width=\"100%\" height=\"100%\" fill=\"rgb({red},{green},{blue})\"/>\
+     <text x=\"50%\" y=\"50%\" dominant-baseline=\"central\" \
+     text-anchor=\"middle\" font-family=\"sans-serif\" \
+     font-size=\"{font_size}\" fill=\"#fff\">{initials}</text></svg>"
+  )
+}
@@ -0,0 +1,473 @@
+//! Parsing generated image URLs back into their parts.
+
+use crate::{Error, Generator};
+
+/// The parts of a parsed image URL, see [`ParsedUrl::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParsedUrl {
+  /// The base URL, including any path before the hash except `/avatar`.
+  pub base_url: String,
+
+  /// The file extension without the leading dot, if there is one.
+  pub file_extension: Option<String>,
+
+  /// The decoded fragment, if there is one.
+  pub fragment: Option<String>,
+
+  /// The email hash.
+  pub hash: String,
+
+  /// The decoded query parameters in the order they appear.
+  pub query: Vec<(String, String)>,
+
+  /// Whether the hash is followed by a `@2x` retina suffix.
+  pub retina_suffix: bool,
+
+  /// The URL scheme, like `https`.
+  pub scheme: String,
+}
+
+impl ParsedUrl {
+  /// Parses an image URL in the format [`Generator::generate`] produces.
+  ///
+  /// ```rust
+  /// use gravatar_rs::ParsedUrl;
+  ///
+  /// let parsed = ParsedUrl::parse(
+  ///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?s=128",
+  /// )?;
+  ///
+  /// assert_eq!(parsed.hash, "ebff9105dce4954b1bdb57fdab079ff3");
+  /// assert_eq!(parsed.query, vec![("s".to_string(), "128".to_string())]);
+  /// # Ok::<(), gravatar_rs::Error>(())
+  /// ```
+  pub fn parse(url: &str) -> Result<Self, Error> {
+    let invalid = || Error::InvalidUrl(url.to_string());
+
+    let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+    let (rest, fragment) = match rest.split_once('#') {
+      Some((rest, fragment)) => (rest, Some(decode(fragment)?)),
+      None => (rest, None),
+    };
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let (base_url, file) = path.rsplit_once('/').ok_or_else(invalid)?;
+    let base_url = base_url.strip_suffix("/avatar").unwrap_or(base_url);
+    let (hash, file_extension) = match file.split_once('.') {
+      Some((hash, extension)) => (hash, Some(extension.to_string())),
+      None => (file, None),
+    };
+
+    let (hash, retina_suffix) = match hash.strip_suffix("@2x") {
+      Some(hash) => (hash, true),
+      None => (hash, false),
+    };
+
+    if scheme.is_empty() || base_url.is_empty() || hash.is_empty() {
+      return Err(invalid());
+    }
+
+    Ok(Self {
+      base_url: base_url.to_string(),
+      file_extension,
+      fragment,
+      hash: hash.to_string(),
+      query: parse_query(query)?,
+      retina_suffix,
+      scheme: scheme.to_string(),
+    })
+  }
+}
+
+impl Generator {
+  /// Generates an image URL and verifies that the hash parsed back out of it
+  /// matches the email's hash, guarding against URL assembly bugs.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let url = Generator::default().generate_and_verify("helllo@holllo.cc")?;
+  /// # Ok::<(), gravatar_rs::Error>(())
+  /// ```
+  pub fn generate_and_verify(&self, email: &str) -> Result<String, Error> {
+    let url = self.generate(email);
+    let parsed = self.parse_own_url(&url)?;
+    let expected = self.url_hash(&self.hash(email)).to_string();
+
+    if parsed.hash == expected {
+      Ok(url)
+    } else {
+      Err(Error::HashMismatch {
+        actual: parsed.hash,
+        expected,
+      })
+    }
+  }
+
+  /// Parses a URL generated by this Generator, splitting the hash and file
+  /// extension on the configured extension separator instead of `.`, and
+  /// matching the URL against the URL template when one is set.
+  fn parse_own_url(&self, url: &str) -> Result<ParsedUrl, Error> {
+    if let Some(template) = &self.url_template {
+      return self.parse_templated_url(url, template);
+    }
+
+    let mut parsed = ParsedUrl::parse(url)?;
+    if self.extension_separator == "." {
+      return Ok(parsed);
+    }
+
+    let mut file = parsed.hash.clone();
+    if parsed.retina_suffix {
+      file.push_str("@2x");
+    }
+    if let Some(extension) = &parsed.file_extension {
+      file = format!("{file}.{extension}");
+    }
+
+    let extension = format!("{}jpg", self.extension_separator);
+    let (file, file_extension) = match file.strip_suffix(&extension) {
+      Some(file) => (file, Some("jpg".to_string())),
+      None => (file.as_str(), None),
+    };
+    let (hash, retina_suffix) = match file.strip_suffix("@2x") {
+      Some(hash) => (hash, true),
+      None => (file, false),
+    };
+
+    parsed.hash = hash.to_string();
+    parsed.file_extension = file_extension;
+    parsed.retina_suffix = retina_suffix;
+    Ok(parsed)
+  }
+
+  /// Parses a URL generated from a URL template by matching the template's
+  /// text and filling in its placeholders from the URL. Placeholders missing
+  /// from the template take the Generator's own scheme and base URL.
+  fn parse_templated_url(
+    &self,
+    url: &str,
+    template: &str,
+  ) -> Result<ParsedUrl, Error> {
+    let invalid = || Error::InvalidUrl(url.to_string());
+
+    let (rest, fragment) = match url.split_once('#') {
+      Some((rest, fragment)) => (rest, Some(decode(fragment)?)),
+      None => (url, None),
+    };
+
+    let extension = self.file_extension();
+    let mut captures = vec![];
+    if !match_template(
+      &template_parts(template),
+      rest,
+      &extension,
+      &mut captures,
+    ) {
+      return Err(invalid());
+    }
+
+    let capture = |name: &str| {
+      captures
+        .iter()
+        .find(|(placeholder, _)| *placeholder == name)
+        .map(|(_, value)| *value)
+    };
+
+    let hash = capture("hash").ok_or_else(invalid)?;
+    let (hash, retina_suffix) = match hash.strip_suffix("@2x") {
+      Some(hash) => (hash, true),
+      None => (hash, false),
+    };
+    let query = capture("query").unwrap_or_default();
+
+    Ok(ParsedUrl {
+      base_url: capture("host").unwrap_or(&self.base_url).to_string(),
+      file_extension: capture("ext")
+        .filter(|extension| !extension.is_empty())
+        .map(|_| "jpg".to_string()),
+      fragment,
+      hash: hash.to_string(),
+      query: parse_query(query.trim_start_matches('?'))?,
+      retina_suffix,
+      scheme: capture("scheme").unwrap_or(&self.scheme).to_string(),
+    })
+  }
+}
+
+/// A part of a URL template, see [`Generator::set_url_template`].
+#[derive(Debug, Clone, Copy)]
+enum TemplatePart<'a> {
+  /// Text that appears in the URL as-is.
+  Literal(&'a str),
+
+  /// A placeholder name without its braces, like `hash`.
+  Placeholder(&'static str),
+}
+
+/// The placeholders [`Generator::set_url_template`] replaces.
+const PLACEHOLDERS: [&str; 5] = ["scheme", "host", "hash", "ext", "query"];
+
+/// Splits a URL template into its literal text and placeholders.
+fn template_parts(template: &str) -> Vec<TemplatePart<'_>> {
+  let mut parts = vec![];
+  let mut literal_start = 0;
+  let mut index = 0;
+
+  while index < template.len() {
+    let placeholder = PLACEHOLDERS.into_iter().find(|name| {
+      template[index..]
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_prefix(name))
+        .is_some_and(|rest| rest.starts_with('}'))
+    });
+
+    match placeholder {
+      Some(name) => {
+        if literal_start < index {
+          parts.push(TemplatePart::Literal(&template[literal_start..index]));
+        }
+        parts.push(TemplatePart::Placeholder(name));
+        index += name.len() + 2;
+        literal_start = index;
+      }
+      None => {
+        index += template[index..].chars().next().map_or(1, char::len_utf8);
+      }
+    }
+  }
+
+  if literal_start < template.len() {
+    parts.push(TemplatePart::Literal(&template[literal_start..]));
+  }
+
+  parts
+}
+
+/// Matches a URL against template parts, trying the shortest value for every
+/// placeholder first and collecting the placeholder values on success.
+fn match_template<'a>(
+  parts: &[TemplatePart<'_>],
+  url: &'a str,
+  extension: &str,
+  captures: &mut Vec<(&'static str, &'a str)>,
+) -> bool {
+  let Some((part, rest)) = parts.split_first() else {
+    return url.is_empty();
+  };
+
+  let name = match part {
+    TemplatePart::Literal(literal) => {
+      return url
+        .strip_prefix(literal)
+        .is_some_and(|url| match_template(rest, url, extension, captures));
+    }
+    TemplatePart::Placeholder(name) => *name,
+  };
+
+  for end in (0..=url.len()).filter(|end| url.is_char_boundary(*end)) {
+    let value = &url[..end];
+    let valid = match name {
+      "scheme" => {
+        !value.is_empty()
+          && value
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || "+-.".contains(char))
+      }
+      "host" => !value.is_empty() && !value.contains('?'),
+      "hash" => !value.is_empty() && !value.contains(['/', '?']),
+      "ext" => value.is_empty() || value == extension,
+      _ => value.is_empty() || value.starts_with('?'),
+    };
+
+    if !valid {
+      continue;
+    }
+
+    captures.push((name, value));
+    if match_template(rest, &url[end..], extension, captures) {
+      return true;
+    }
+    captures.pop();
+  }
+
+  false
+}
+
+/// The options that can be recovered from a generated URL, used by
+/// [`Generator::assert_roundtrip`].
+#[derive(Debug, Default, PartialEq)]
+struct UrlOptions {
+  /// The base URL.
+  base_url: String,
+
+  /// The crop mode, from `crop=`.
+  crop_mode: Option<String>,
+
+  /// The default image, from `d=`.
+  default_image: Option<String>,
+
+  /// The width and height, from `w=` and `h=`.
+  dimensions: Option<(u32, u32)>,
+
+  /// The experiment flag, from `exp=`.
+  experiment: Option<String>,
+
+  /// Whether the file extension is included.
+  file_extension: bool,
+
+  /// Whether the default image is forced, from `f=y`.
+  force_default: bool,
+
+  /// The fragment.
+  fragment: Option<String>,
+
+  /// The image size, from `s=`.
+  image_size: Option<i32>,
+
+  /// Whether the original image is requested, from `original=1`.
+  original: bool,
+
+  /// The rating, from `r=`.
+  rating: Option<String>,
+
+  /// Whether the `@2x` retina suffix is included.
+  retina_suffix: bool,
+
+  /// The scheme.
+  scheme: String,
+
+  /// Whether metadata stripping is requested, from `strip=1`.
+  strip_metadata: bool,
+
+  /// Whether a token is included, from `token=`.
+  token: bool,
+}
+
+impl Generator {
+  /// Generates an image URL, parses it back and checks that every option
+  /// recovered from it matches the Generator's effective options. The email
+  /// itself can't be recovered, but its hash is checked as well.
+  ///
+  /// URLs from a [URL template](Generator::set_url_template) are parsed by
+  /// matching them against the template, so options the template leaves out,
+  /// like the query parameters without `{query}`, are reported as mismatches.
+  ///
+  /// Returns a description of every mismatch when the round trip fails.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default().set_image_size(128).set_rating("pg");
+  ///
+  /// assert_eq!(generator.assert_roundtrip("helllo@holllo.cc"), Ok(()));
+  /// ```
+  pub fn assert_roundtrip(&self, email: &str) -> Result<(), String> {
+    let url = self.generate(email);
+    let parsed = self
+      .parse_own_url(&url)
+      .map_err(|error| error.to_string())?;
+
+    let mut problems = vec![];
+    let expected_hash = self.url_hash(&self.hash(email)).to_string();
+    if parsed.hash != expected_hash {
+      problems.push(format!(
+        "hash: expected {expected_hash:?}, found {:?}",
+        parsed.hash
+      ));
+    }
+
+    let mut found = UrlOptions {
+      base_url: parsed.base_url,
+      file_extension: parsed.file_extension.as_deref() == Some("jpg"),
+      fragment: parsed.fragment,
+      retina_suffix: parsed.retina_suffix,
+      scheme: parsed.scheme,
+      ..Default::default()
+    };
+
+    let (mut width, mut height) = (None, None);
+    for (key, value) in parsed.query {
+      let Some(key) = key.strip_prefix(&self.param_prefix) else {
+        problems.push(format!("query parameter {key:?} is missing the prefix"));
+        continue;
+      };
+
+      match key {
+        "crop" => found.crop_mode = Some(value),
+        "d" => found.default_image = Some(value),
+        "exp" => found.experiment = Some(value),
+        "f" => found.force_default = value == "y",
+        "h" => height = value.parse().ok(),
+        "original" => found.original = value == "1",
+        "r" => found.rating = Some(value),
+        "s" => found.image_size = value.parse().ok(),
+        "strip" => found.strip_metadata = value == "1",
+        "token" => found.token = true,
+        "w" => width = value.parse().ok(),
+        _ => problems.push(format!("unexpected query parameter {key:?}")),
+      }
+    }
+    found.dimensions = width.zip(height);
+
+    let path = self.path_style.path(&self.hash(email), &expected_hash);
+    let path_prefix = path.rsplit_once('/').map_or("", |(prefix, _)| prefix);
+    let expected_base_url = match path_prefix {
+      _ if self.url_template.is_some() => self.base_url.clone(),
+      "avatar" => self.base_url.clone(),
+      prefix => format!("{}/{prefix}", self.base_url),
+    };
+
+    let expected = UrlOptions {
+      base_url: expected_base_url,
+      crop_mode: self.crop_mode.map(|mode| mode.as_str().to_string()),
+      default_image: self
+        .with_fallback_pool(&self.hash(email))
+        .effective_default_image(),
+      dimensions: self
+        .effective_dimensions()
+        .filter(|_| !self.request_original),
+      experiment: self.experiment.clone(),
+      file_extension: self.include_file_extension || self.force_jpeg,
+      force_default: self.effective_force_default(),
+      fragment: self.fragment.clone(),
+      image_size: self.effective_square_size(),
+      original: self.request_original,
+      rating: self.rating.clone(),
+      retina_suffix: self.retina_suffix,
+      scheme: self.scheme.clone(),
+      strip_metadata: self.strip_metadata,
+      token: self.token_provider.is_some(),
+    };
+
+    if found != expected {
+      problems.push(format!("expected {expected:?}, found {found:?}"));
+    }
+
+    if problems.is_empty() {
+      Ok(())
+    } else {
+      Err(problems.join("\n"))
+    }
+  }
+}
+
+/// Percent-decodes a part of a URL.
+fn decode(value: &str) -> Result<String, Error> {
+  urlencoding::decode(value)
+    .map(|decoded| decoded.into_owned())
+    .map_err(|_| Error::InvalidUrl(value.to_string()))
+}
+
+/// Parses and decodes a query string without its leading `?`.
+fn parse_query(query: &str) -> Result<Vec<(String, String)>, Error> {
+  query
+    .split('&')
+    .filter(|pair| !pair.is_empty())
+    .map(|pair| {
+      let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+      Ok((decode(key)?, decode(value)?))
+    })
+    .collect()
+}
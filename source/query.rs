@@ -0,0 +1,117 @@
+//! Typed access to the query parameters of a [`Generator`].
+
+use crate::Generator;
+
+/// The query parameter options a [`Generator`] emits, see
+/// [`Generator::query_struct`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryParameters {
+  /// The default image, emitted as `d=`.
+  pub default_image: Option<String>,
+
+  /// Whether the default image is forced, emitted as `f=y`.
+  pub force_default: bool,
+
+  /// The rating, emitted as `r=`.
+  pub rating: Option<String>,
+
+  /// The image size, emitted as `s=`.
+  pub size: Option<i32>,
+}
+
+/// Per-request overrides for a [`Generator`], see
+/// [`Generator::override_query`]. Options that are `None` keep the value of
+/// the Generator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GeneratorOptions {
+  /// Overrides [`Generator::default_image`].
+  pub default_image: Option<String>,
+
+  /// Overrides [`Generator::force_default`].
+  pub force_default: Option<bool>,
+
+  /// Overrides [`Generator::image_size`].
+  pub image_size: Option<i32>,
+
+  /// Overrides [`Generator::rating`].
+  pub rating: Option<String>,
+}
+
+impl Generator {
+  /// Returns the query parameter options the Generator emits as a typed
+  /// struct. Options that are set but not emitted, like the image size when
+  /// dimensions are used instead, are left out. With a non-empty
+  /// [fallback pool](Generator::set_fallback_pool) the default image depends
+  /// on the email, so it's left out as well.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let query = Generator::default().set_image_size(128).query_struct();
+  ///
+  /// assert_eq!(query.size, Some(128));
+  /// ```
+  pub fn query_struct(&self) -> QueryParameters {
+    QueryParameters {
+      default_image: self.effective_default_image(),
+      force_default: self.effective_force_default(),
+      rating: self.rating.clone(),
+      size: self.effective_square_size(),
+    }
+  }
+
+  /// Returns only the query parameters that `overrides` would add or change
+  /// compared to the Generator, joined with `&` and without a leading `?`.
+  /// The token provider isn't called, as tokens are never part of the
+  /// difference. A non-empty [fallback pool](Generator::set_fallback_pool)
+  /// takes precedence over default image overrides, so they're left out.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, GeneratorOptions};
+  ///
+  /// let generator = Generator::default().set_rating("pg");
+  /// let mut overrides = GeneratorOptions::default();
+  /// overrides.image_size = Some(128);
+  ///
+  /// assert_eq!(generator.override_query(&overrides), "s=128");
+  /// ```
+  pub fn override_query(&self, overrides: &GeneratorOptions) -> String {
+    let base = Self {
+      token_provider: None,
+      ..self.clone()
+    };
+    let mut overridden = base.clone();
+
+    if let Some(default_image) = &overrides.default_image {
+      overridden.default_image = Some(default_image.clone());
+    }
+
+    if let Some(force_default) = overrides.force_default {
+      overridden.force_default = force_default;
+    }
+
+    if let Some(image_size) = overrides.image_size {
+      overridden.image_size = Some(image_size);
+    }
+
+    if let Some(rating) = &overrides.rating {
+      overridden.rating = Some(rating.clone());
+    }
+
+    let base_query = base.query_parameters();
+    let base_parameters = base_query.trim_start_matches('?').split('&');
+    let base_parameters = base_parameters.collect::<Vec<_>>();
+
+    overridden
+      .query_parameters()
+      .trim_start_matches('?')
+      .split('&')
+      .filter(|parameter| {
+        !parameter.is_empty() && !base_parameters.contains(parameter)
+      })
+      .collect::<Vec<_>>()
+      .join("&")
+  }
+}
@@ -0,0 +1,43 @@
+//! Everything a UI needs to render an avatar, see [`Generator::render_info`].
+
+use crate::{monogram::initials, Generator};
+
+/// The information for rendering an avatar with a colored placeholder, see
+/// [`Generator::render_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderInfo {
+  /// The image URL, from [`Generator::generate`].
+  pub url: String,
+
+  /// A stable cache key, the unquoted value of [`Generator::etag_for`].
+  pub cache_key: String,
+
+  /// The placeholder color, from [`Generator::avatar_color`].
+  pub placeholder_color: (u8, u8, u8),
+
+  /// Up to two initials of the name, or `?` when the name is empty.
+  pub initials: String,
+}
+
+impl Generator {
+  /// Returns the URL, cache key, placeholder color and initials for rendering
+  /// the avatar of an email with a colored placeholder.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let info = Generator::default().render_info("helllo@holllo.cc", "Holllo");
+  ///
+  /// assert_eq!(info.placeholder_color, (235, 255, 145));
+  /// assert_eq!(info.initials, "H");
+  /// ```
+  pub fn render_info(&self, email: &str, name: &str) -> RenderInfo {
+    RenderInfo {
+      url: self.generate(email),
+      cache_key: self.cache_key(email),
+      placeholder_color: Self::avatar_color(email),
+      initials: initials(name),
+    }
+  }
+}
@@ -0,0 +1,98 @@
+//! Spreading generated URLs across multiple base URLs, see
+//! [`RoundRobinGenerator`] and [`Generator::generate_sticky_mirror`].
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{pick_index, Generator};
+
+/// A wrapper around a [`Generator`] that cycles through multiple base URLs,
+/// for spreading avatar requests across mirrors.
+///
+/// ```rust
+/// use gravatar_rs::{Generator, RoundRobinGenerator};
+///
+/// let generator = RoundRobinGenerator::new(
+///   Generator::default(),
+///   vec!["a.example.com".to_string(), "b.example.com".to_string()],
+/// );
+///
+/// let first = generator.next_url("helllo@holllo.cc");
+/// let second = generator.next_url("helllo@holllo.cc");
+///
+/// assert!(first.starts_with("https://a.example.com/"));
+/// assert!(second.starts_with("https://b.example.com/"));
+/// ```
+#[derive(Debug)]
+pub struct RoundRobinGenerator {
+  /// The base URLs to cycle through.
+  base_urls: Vec<String>,
+
+  /// The number of URLs generated so far, used to pick the next base URL.
+  counter: AtomicUsize,
+
+  /// The Generator used for everything except the base URL.
+  generator: Generator,
+}
+
+impl RoundRobinGenerator {
+  /// Creates a new RoundRobinGenerator. When `base_urls` is empty, the
+  /// Generator's own base URL is always used.
+  pub fn new(generator: Generator, base_urls: Vec<String>) -> Self {
+    Self {
+      base_urls,
+      counter: AtomicUsize::new(0),
+      generator,
+    }
+  }
+
+  /// Returns the base URLs this RoundRobinGenerator cycles through.
+  pub fn base_urls(&self) -> &[String] {
+    &self.base_urls
+  }
+
+  /// Generates a URL for an email using the next base URL in the cycle.
+  pub fn next_url(&self, email: &str) -> String {
+    let hash = self.generator.hash(email);
+
+    if self.base_urls.is_empty() {
+      return self.generator.build_url(&self.generator.base_url, &hash);
+    }
+
+    let index = self.counter.fetch_add(1, Ordering::Relaxed);
+    let base_url = &self.base_urls[index % self.base_urls.len()];
+    self.generator.build_url(base_url, &hash)
+  }
+}
+
+impl Generator {
+  /// Generates a URL for an email using one of `mirrors` as the base URL. The
+  /// mirror is picked from the email's hash, so the same email always uses
+  /// the same mirror while different emails spread across all of them. When
+  /// `mirrors` is empty, the Generator's own base URL is used.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let generator = Generator::default();
+  /// let mirrors = ["a.example.com", "b.example.com"];
+  ///
+  /// assert_eq!(
+  ///   generator.generate_sticky_mirror("helllo@holllo.cc", &mirrors),
+  ///   generator.generate_sticky_mirror("HELLLO@holllo.cc", &mirrors),
+  /// );
+  /// ```
+  pub fn generate_sticky_mirror(
+    &self,
+    email: &str,
+    mirrors: &[&str],
+  ) -> String {
+    let hash = self.hash(email);
+
+    if mirrors.is_empty() {
+      return self.build_url(&self.base_url, &hash);
+    }
+
+    let index = pick_index(&hash, mirrors.len());
+    self.build_url(mirrors[index], &hash)
+  }
+}
@@ -0,0 +1,165 @@
+//! A validated, immutable [`SealedGenerator`].
+
+use crate::{
+  Generator, GeneratorError, PathStyle, MAX_IMAGE_SIZE, MIN_IMAGE_SIZE, RATINGS,
+};
+
+/// A [`Generator`] whose configuration has been validated and can no longer
+/// be changed, created with [`Generator::seal`].
+///
+/// ```rust
+/// use gravatar_rs::Generator;
+///
+/// let sealed = Generator::default().set_image_size(128).seal().unwrap();
+///
+/// assert_eq!(
+///   sealed.generate("helllo@holllo.cc"),
+///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?s=128"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct SealedGenerator {
+  /// The validated Generator.
+  generator: Generator,
+}
+
+impl SealedGenerator {
+  /// Hashes an email with the sealed Generator's [`Hasher`](crate::Hasher)
+  /// and tenant salt, the same hash used in its URLs.
+  ///
+  /// ```rust
+  /// use gravatar_rs::Generator;
+  ///
+  /// let sealed = Generator::default().seal().unwrap();
+  ///
+  /// assert_eq!(
+  ///   sealed.hash_email("helllo@holllo.cc"),
+  ///   "ebff9105dce4954b1bdb57fdab079ff3"
+  /// );
+  /// ```
+  pub fn hash_email(&self, email: &str) -> String {
+    self.generator.hash(email)
+  }
+
+  /// Generates a new image URL, see [`Generator::generate`].
+  pub fn generate(&self, email: &str) -> String {
+    self.generator.generate(email)
+  }
+}
+
+impl Generator {
+  /// Validates the Generator's configuration and returns a
+  /// [`SealedGenerator`], or all the problems that were found.
+  ///
+  /// ```rust
+  /// use gravatar_rs::{Generator, GeneratorError};
+  ///
+  /// let errors = Generator::default().set_image_size(0).seal().unwrap_err();
+  ///
+  /// assert_eq!(errors, vec![GeneratorError::InvalidImageSize(0)]);
+  /// ```
+  pub fn seal(self) -> Result<SealedGenerator, Vec<GeneratorError>> {
+    let errors = self.validate();
+
+    if errors.is_empty() {
+      Ok(SealedGenerator { generator: self })
+    } else {
+      Err(errors)
+    }
+  }
+
+  /// Returns all the problems with the Generator's configuration.
+  pub(crate) fn validate(&self) -> Vec<GeneratorError> {
+    let mut errors = vec![];
+
+    if self.base_url.is_empty() {
+      errors.push(GeneratorError::EmptyBaseUrl);
+    }
+
+    if let Some(default_image) = &self.default_image {
+      if !Self::is_valid_default_image(default_image) {
+        errors.push(GeneratorError::InvalidDefaultImage(default_image.clone()));
+      }
+    }
+
+    for default_image in &self.fallback_pool {
+      if !Self::is_valid_default_image(default_image) {
+        errors.push(GeneratorError::InvalidDefaultImage(default_image.clone()));
+      }
+    }
+
+    if self.has_force_default_conflict() {
+      errors.push(GeneratorError::ForceDefaultWithoutDefaultImage);
+    }
+
+    if let Some((width, height)) = self.dimensions {
+      let range = MIN_IMAGE_SIZE as u32..=MAX_IMAGE_SIZE as u32;
+      if !range.contains(&width) || !range.contains(&height) {
+        errors.push(GeneratorError::InvalidDimensions(width, height));
+      }
+    }
+
+    let separator = &self.extension_separator;
+    if !is_unreserved(separator) {
+      errors.push(GeneratorError::InvalidExtensionSeparator(separator.clone()));
+    }
+
+    if let Some(length) = self.hash_length {
+      let full_length = self.hasher.hash("").len();
+      if length == 0 || length % 2 != 0 || length > full_length {
+        errors.push(GeneratorError::InvalidHashLength(length));
+      }
+    }
+
+    if let Some(image_size) = self.image_size {
+      if !(MIN_IMAGE_SIZE..=MAX_IMAGE_SIZE).contains(&image_size) {
+        errors.push(GeneratorError::InvalidImageSize(image_size));
+      }
+    }
+
+    if let Some(size_cap) = self.size_cap {
+      if !(MIN_IMAGE_SIZE..=MAX_IMAGE_SIZE).contains(&size_cap) {
+        errors.push(GeneratorError::InvalidSizeCap(size_cap));
+      }
+    }
+
+    if self.request_original
+      && (self.image_size.is_some() || self.dimensions.is_some())
+    {
+      errors.push(GeneratorError::OriginalWithImageSize);
+    }
+
+    if let Some(rating) = &self.rating {
+      if !RATINGS.contains(&rating.to_lowercase().as_str()) {
+        errors.push(GeneratorError::InvalidRating(rating.clone()));
+      }
+    }
+
+    if let PathStyle::Bucketed(bucket) = &self.path_style {
+      if !is_unreserved(bucket) {
+        errors.push(GeneratorError::InvalidBucket(bucket.clone()));
+      }
+    }
+
+    if !["https", "http"].contains(&self.scheme.as_str()) {
+      errors.push(GeneratorError::InvalidScheme(self.scheme.clone()));
+    }
+
+    if let Some(template) = &self.url_template {
+      if !template.contains("{hash}") {
+        errors.push(GeneratorError::InvalidUrlTemplate(template.clone()));
+      }
+    }
+
+    errors
+  }
+}
+
+/// Returns whether a value is non-empty and only contains unreserved URL
+/// characters, which are ASCII letters, digits, `-`, `.`, `_` and `~`.
+fn is_unreserved(value: &str) -> bool {
+  !value.is_empty()
+    && value
+      .bytes()
+      .all(|byte| byte.is_ascii_alphanumeric() || b"-._~".contains(&byte))
+}
@@ -0,0 +1,62 @@
+//! A request-scoped [`Session`] that memoizes generated URLs.
+
+use std::collections::HashMap;
+
+use crate::Generator;
+
+/// A wrapper around a [`Generator`] that caches generated URLs per email, so
+/// repeated emails within the same session are only generated once.
+///
+/// ```rust
+/// use gravatar_rs::{Generator, Session};
+///
+/// let mut session = Session::new(Generator::default());
+///
+/// assert_eq!(
+///   session.url("helllo@holllo.cc"),
+///   "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Session {
+  /// The cache of previously generated URLs, keyed by email.
+  cache: HashMap<String, String>,
+
+  /// The Generator used for emails that aren't cached yet.
+  generator: Generator,
+}
+
+impl Session {
+  /// Creates a new Session with an empty cache.
+  pub fn new(generator: Generator) -> Self {
+    Self {
+      cache: HashMap::new(),
+      generator,
+    }
+  }
+
+  /// Returns the Generator used by this Session.
+  pub fn generator(&self) -> &Generator {
+    &self.generator
+  }
+
+  /// Returns the URL for an email, generating and caching it when the email
+  /// hasn't been seen before in this Session.
+  ///
+  /// When the Generator has a [token provider](Generator::set_token_provider)
+  /// the URL is generated on every call instead, so every URL gets a fresh
+  /// token.
+  pub fn url(&mut self, email: &str) -> &str {
+    let generator = &self.generator;
+    let regenerate = generator.token_provider.is_some();
+    self
+      .cache
+      .entry(email.to_string())
+      .and_modify(|url| {
+        if regenerate {
+          *url = generator.generate(email);
+        }
+      })
+      .or_insert_with(|| generator.generate(email))
+  }
+}
@@ -1,4 +1,10 @@
-use gravatar_rs::Generator;
+use std::{io::Write, sync::atomic::Ordering};
+
+use gravatar_rs::{
+  CropMode, Generator, GeneratorError, GeneratorOptions, HashDisplay, Hasher,
+  Md5Hasher, ParsedUrl, PathStyle, QueryParameters, Rating,
+  RoundRobinGenerator, Session, Sha256Hasher,
+};
 
 const BAUKE_EMAIL: &str = "me@bauke.xyz";
 const HOLLLO_EMAIL: &str = "helllo@holllo.cc";
@@ -56,3 +62,1775 @@ fn test_all_options() {
   let urls = [BAUKE_EMAIL, HOLLLO_EMAIL].map(|email| generator.generate(email));
   insta::assert_debug_snapshot!("generate-options", urls);
 }
+
+#[test]
+fn test_param_prefix() {
+  let generator = Generator::default()
+    .set_param_prefix("av_")
+    .set_image_size(128)
+    .set_rating("pg");
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?av_s=128&av_r=pg"
+  );
+}
+
+#[test]
+fn test_session() {
+  let mut session = Session::new(Generator::default().set_image_size(128));
+
+  let first = session.url(HOLLLO_EMAIL).to_string();
+  let first_ptr = session.url(HOLLLO_EMAIL).as_ptr();
+  let second_ptr = session.url(HOLLLO_EMAIL).as_ptr();
+
+  assert_eq!(first, session.generator().generate(HOLLLO_EMAIL));
+  assert_eq!(first_ptr, second_ptr);
+  assert_ne!(session.url(BAUKE_EMAIL), first);
+
+  let counter = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+  let provider_counter = counter.clone();
+  let mut session =
+    Session::new(Generator::default().set_token_provider(move || {
+      provider_counter.fetch_add(1, Ordering::SeqCst).to_string()
+    }));
+
+  assert!(session.url(HOLLLO_EMAIL).ends_with("?token=0"));
+  assert!(session.url(HOLLLO_EMAIL).ends_with("?token=1"));
+  assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_cache_control() {
+  let public = Generator::default();
+  assert_eq!(
+    public.cache_control_for(HOLLLO_EMAIL),
+    "public, max-age=86400"
+  );
+
+  let private = Generator::default().set_private(true);
+  assert_eq!(
+    private.cache_control_for(HOLLLO_EMAIL),
+    "private, max-age=0"
+  );
+}
+
+#[test]
+fn test_fragment() {
+  let generator = Generator::default()
+    .set_image_size(128)
+    .set_fragment(Some("/users/holllo avatar"));
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?s=128#/users/holllo%20avatar"
+  );
+}
+
+#[test]
+fn test_seal() {
+  let errors = Generator::default()
+    .set_base_url("")
+    .set_image_size(4096)
+    .set_rating("nc-17")
+    .seal()
+    .unwrap_err();
+
+  assert_eq!(
+    errors,
+    vec![
+      GeneratorError::EmptyBaseUrl,
+      GeneratorError::InvalidImageSize(4096),
+      GeneratorError::InvalidRating("nc-17".to_string()),
+    ]
+  );
+
+  let generator = Generator::default().set_image_size(128).set_rating("pg");
+  let expected = generator.generate(HOLLLO_EMAIL);
+  let sealed = generator.seal().unwrap();
+  assert_eq!(sealed.generate(HOLLLO_EMAIL), expected);
+}
+
+#[test]
+fn test_dimensions() {
+  let generator = Generator::default()
+    .set_image_size(128)
+    .set_dimensions(160, 90);
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?w=160&h=90"
+  );
+
+  let errors = generator.set_dimensions(0, 4096).seal().unwrap_err();
+  assert_eq!(errors, vec![GeneratorError::InvalidDimensions(0, 4096)]);
+}
+
+#[test]
+fn test_color_var() {
+  let generator = Generator::default().set_image_size(64);
+  let css = generator.generate_with_color_var(BAUKE_EMAIL, "accent");
+
+  let (red, green, blue) = Generator::avatar_color(BAUKE_EMAIL);
+  assert!(
+    css.starts_with(&format!("--accent: #{red:02x}{green:02x}{blue:02x};"))
+  );
+  assert!(css.ends_with(&format!(
+    "background-image: url(\"{}\");",
+    generator.generate(BAUKE_EMAIL)
+  )));
+}
+
+#[test]
+fn test_manifest() {
+  let mut file = tempfile::NamedTempFile::new().unwrap();
+  writeln!(file, "{BAUKE_EMAIL}\n\n  \n{HOLLLO_EMAIL}").unwrap();
+
+  let generator = Generator::default();
+  let manifest = generator.generate_manifest(file.path()).unwrap();
+
+  assert_eq!(
+    manifest,
+    vec![
+      (BAUKE_EMAIL.to_string(), generator.generate(BAUKE_EMAIL)),
+      (HOLLLO_EMAIL.to_string(), generator.generate(HOLLLO_EMAIL)),
+    ]
+  );
+
+  assert!(generator.generate_manifest("does-not-exist.txt").is_err());
+}
+
+#[test]
+fn test_rating_numeric() {
+  let generator = Generator::default().set_rating_numeric(1);
+  assert_eq!(generator.rating.as_deref(), Some("pg"));
+  assert!(generator.generate(HOLLLO_EMAIL).ends_with("?r=pg"));
+
+  let errors = generator.set_rating_numeric(4).seal().unwrap_err();
+  assert_eq!(errors, vec![GeneratorError::InvalidRating("4".to_string())]);
+}
+
+#[test]
+fn test_query_struct() {
+  let generator = Generator::default()
+    .set_default_image("identicon")
+    .set_force_default(true)
+    .set_image_size(128)
+    .set_rating("pg");
+
+  let query = generator.query_struct();
+  assert_eq!(query.default_image.as_deref(), Some("identicon"));
+  assert!(query.force_default);
+  assert_eq!(query.rating.as_deref(), Some("pg"));
+  assert_eq!(query.size, Some(128));
+
+  let query = generator
+    .clone()
+    .set_dimensions(160, 90)
+    .set_default_image_size(64)
+    .query_struct();
+  assert_eq!(query.size, None);
+  assert_eq!(query.default_image.as_deref(), Some("identicon"));
+
+  let query = Generator::default()
+    .set_force_default(true)
+    .set_force_requires_default(true)
+    .query_struct();
+  assert!(!query.force_default);
+
+  assert_eq!(
+    Generator::default().query_struct(),
+    QueryParameters::default()
+  );
+}
+
+#[test]
+fn test_color_palette() {
+  let palette = Generator::avatar_color_palette(HOLLLO_EMAIL, 4);
+  assert_eq!(palette.len(), 4);
+  assert_eq!(
+    palette,
+    Generator::avatar_color_palette(&HOLLLO_EMAIL.to_uppercase(), 4)
+  );
+
+  for (index, color) in palette.iter().enumerate() {
+    assert!(!palette[index + 1..].contains(color));
+  }
+
+  assert!(Generator::avatar_color_palette(HOLLLO_EMAIL, 0).is_empty());
+}
+
+#[test]
+fn test_valid_default_image() {
+  assert!(Generator::is_valid_default_image("identicon"));
+  assert!(Generator::is_valid_default_image(
+    "https://example.com/avatars/default.png?size=80"
+  ));
+  assert!(!Generator::is_valid_default_image(
+    "https://exa mple.com/a.png"
+  ));
+  assert!(!Generator::is_valid_default_image("example.com/a.png"));
+  assert!(!Generator::is_valid_default_image("unicorn"));
+
+  let errors = Generator::default()
+    .set_default_image("unicorn")
+    .seal()
+    .unwrap_err();
+  assert_eq!(
+    errors,
+    vec![GeneratorError::InvalidDefaultImage("unicorn".to_string())]
+  );
+}
+
+#[test]
+fn test_amp_img() {
+  let element = Generator::default()
+    .set_default_image("https://example.com/a.png?x=1&y=2")
+    .generate_amp_img(HOLLLO_EMAIL, "<Holllo & \"friends\">");
+
+  assert!(element.starts_with("<amp-img src=\"https://www.gravatar.com/"));
+  assert!(element.contains("width=\"80\" height=\"80\""));
+  assert!(element.contains("alt=\"&lt;Holllo &amp; &quot;friends&quot;&gt;\""));
+  assert!(element.ends_with("layout=\"responsive\"></amp-img>"));
+}
+
+#[test]
+fn test_round_robin() {
+  let hosts = ["a.example.com", "b.example.com", "c.example.com"];
+  let generator = RoundRobinGenerator::new(
+    Generator::default(),
+    hosts.map(ToString::to_string).to_vec(),
+  );
+
+  for host in hosts.iter().chain(hosts.iter()) {
+    let url = generator.next_url(HOLLLO_EMAIL);
+    assert_eq!(
+      url,
+      Generator::default()
+        .set_base_url(host)
+        .generate(HOLLLO_EMAIL)
+    );
+  }
+}
+
+#[test]
+fn test_default_image_size() {
+  let generator = Generator::default()
+    .set_default_image("https://example.com/d.png")
+    .set_default_image_size(80);
+
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?d=https%3A%2F%2Fexample.com%2Fd.png%3Fsize%3D80"));
+
+  let generator = generator.set_default_image("https://example.com/d?v=2");
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?d=https%3A%2F%2Fexample.com%2Fd%3Fv%3D2%26size%3D80"));
+
+  let generator = generator.set_default_image("identicon");
+  assert!(generator.generate(HOLLLO_EMAIL).ends_with("?d=identicon"));
+}
+
+#[test]
+fn test_generate_and_verify() {
+  let generator = Generator::default()
+    .set_base_url("cdn.libravatar.org/mirror")
+    .set_default_image("https://example.com/d.png")
+    .set_include_file_extension(true)
+    .set_fragment(Some("top"));
+
+  for email in [BAUKE_EMAIL, HOLLLO_EMAIL] {
+    let url = generator.generate_and_verify(email).unwrap();
+    assert_eq!(url, generator.generate(email));
+
+    let parsed = ParsedUrl::parse(&url).unwrap();
+    assert_eq!(parsed.base_url, "cdn.libravatar.org/mirror");
+    assert_eq!(parsed.file_extension.as_deref(), Some("jpg"));
+    assert_eq!(parsed.fragment.as_deref(), Some("top"));
+    assert_eq!(
+      parsed.query,
+      vec![("d".to_string(), "https://example.com/d.png".to_string())]
+    );
+  }
+
+  assert!(ParsedUrl::parse("not a url").is_err());
+}
+
+#[test]
+fn test_jsonld() {
+  let json = Generator::default()
+    .set_dimensions(160, 90)
+    .generate_jsonld(BAUKE_EMAIL);
+
+  insta::assert_snapshot!("jsonld", json);
+}
+
+#[test]
+fn test_srcset() {
+  let generator = Generator::default()
+    .set_image_size(1024)
+    .set_density_descriptors(vec![1.0, 1.5, 3.0]);
+
+  let srcset = generator.generate_srcset(HOLLLO_EMAIL);
+  let entries = srcset.split(", ").collect::<Vec<_>>();
+  assert_eq!(entries.len(), 3);
+  assert!(entries[0].ends_with("?s=1024 1x"));
+  assert!(entries[1].ends_with("?s=1536 1.5x"));
+  assert!(entries[2].ends_with("?s=2048 3x"));
+}
+
+#[test]
+fn test_resolve_batch() {
+  let generator = Generator::default().set_image_size(64).set_rating("pg");
+  let urls = generator.resolve_batch(&[
+    (BAUKE_EMAIL.to_string(), None),
+    (HOLLLO_EMAIL.to_string(), Some(256)),
+    (HOLLLO_EMAIL.to_string(), None),
+  ]);
+
+  assert_eq!(
+    urls,
+    vec![
+      generator.generate(BAUKE_EMAIL),
+      "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3?s=256&r=pg"
+        .to_string(),
+      generator.generate(HOLLLO_EMAIL),
+    ]
+  );
+}
+
+#[test]
+fn test_aspect_ratio() {
+  assert_eq!(Generator::default().set_image_size(128).aspect_ratio(), 1.0);
+  assert_eq!(
+    Generator::default().set_dimensions(160, 90).aspect_ratio(),
+    160.0 / 90.0
+  );
+}
+
+#[test]
+fn test_cache_warm_plan() {
+  let emails = [BAUKE_EMAIL, HOLLLO_EMAIL, " Helllo@Holllo.cc "];
+  let plan = Generator::default().cache_warm_plan(&emails, &[64, 128, 64]);
+
+  assert_eq!(plan.requested, 9);
+  assert_eq!(plan.urls.len(), 4);
+  assert_eq!(plan.duplicates(), 5);
+  assert!(plan.urls[0].ends_with("?s=64"));
+  assert!(plan.urls[3].ends_with("?s=128"));
+}
+
+#[test]
+fn test_hash_prefix() {
+  let hash = Generator::hash_email(BAUKE_EMAIL);
+  assert_eq!(Generator::hash_prefix(BAUKE_EMAIL, 16), hash[..4]);
+  assert_eq!(Generator::hash_prefix(BAUKE_EMAIL, 24), hash[..6]);
+  assert_eq!(Generator::hash_prefix(BAUKE_EMAIL, 256), hash);
+  assert_eq!(Generator::hash_prefix(BAUKE_EMAIL, 0), "");
+
+  // "ec" with the last 3 bits of the "c" cleared.
+  assert_eq!(Generator::hash_prefix(BAUKE_EMAIL, 5), "e8");
+}
+
+#[test]
+fn test_force_requires_default() {
+  let generator = Generator::default().set_force_default(true);
+  assert!(generator.generate(HOLLLO_EMAIL).ends_with("?f=y"));
+  assert_eq!(generator.warnings().len(), 1);
+
+  let generator = generator.set_force_requires_default(true);
+  assert!(!generator.generate(HOLLLO_EMAIL).contains("f=y"));
+  assert_eq!(
+    generator.try_generate(HOLLLO_EMAIL),
+    Err(GeneratorError::ForceDefaultWithoutDefaultImage)
+  );
+
+  let generator = generator.set_default_image("identicon");
+  assert!(generator.warnings().is_empty());
+  assert!(generator
+    .try_generate(HOLLLO_EMAIL)
+    .unwrap()
+    .ends_with("?d=identicon&f=y"));
+
+  let generator = Generator::default()
+    .set_fallback_pool(vec!["https://example.com/a.png".to_string()])
+    .set_force_default(true)
+    .set_force_requires_default(true);
+  assert!(generator.warnings().is_empty());
+  assert!(generator
+    .try_generate(HOLLLO_EMAIL)
+    .unwrap()
+    .ends_with("?d=https%3A%2F%2Fexample.com%2Fa.png&f=y"));
+  assert!(generator.assert_roundtrip(HOLLLO_EMAIL).is_ok());
+  assert!(generator.seal().is_ok());
+}
+
+#[derive(Debug)]
+struct PrefixHasher;
+
+impl Hasher for PrefixHasher {
+  fn hash(&self, normalized_email: &str) -> String {
+    format!("custom-{}", normalized_email.replace(['@', '.'], "-"))
+  }
+}
+
+#[test]
+fn test_hasher() {
+  let generator = Generator::default().set_hasher(Box::new(PrefixHasher));
+  assert_eq!(
+    generator.generate(" Helllo@Holllo.cc "),
+    "https://www.gravatar.com/avatar/custom-helllo-holllo-cc"
+  );
+  assert!(generator.generate_and_verify(HOLLLO_EMAIL).is_ok());
+
+  assert_eq!(
+    Md5Hasher.hash(HOLLLO_EMAIL),
+    Generator::hash_email(HOLLLO_EMAIL)
+  );
+  assert_eq!(
+    Sha256Hasher.hash(""),
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+  );
+  assert_eq!(
+    Sha256Hasher.hash("abc"),
+    "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+  );
+
+  let sealed = Generator::default()
+    .set_hasher(Box::new(Sha256Hasher))
+    .set_tenant_salt("tenant-1")
+    .seal()
+    .unwrap();
+  let hash = sealed.hash_email(HOLLLO_EMAIL);
+  assert_eq!(
+    hash,
+    Sha256Hasher.hash(&format!("8:tenant-1{HOLLLO_EMAIL}"))
+  );
+  assert!(sealed.generate(HOLLLO_EMAIL).ends_with(&hash));
+}
+
+#[test]
+fn test_bytes_roundtrip() {
+  let generator = Generator::default()
+    .set_base_url("cdn.libravatar.org")
+    .set_crawler_user_agents(vec!["archiver".to_string()])
+    .set_crop_mode(CropMode::Entropy)
+    .set_csp_nonce(Some("nonce"))
+    .set_default_image("https://example.com/d.png")
+    .set_default_image_size(64)
+    .set_density_descriptors(vec![1.0, 1.5])
+    .set_dimensions(160, 90)
+    .set_encoding_safe_chars(":/")
+    .set_experiment(Some("b"))
+    .set_extension_separator("-")
+    .set_fallback_pool(vec![
+      "https://example.com/a.png".to_string(),
+      "https://example.com/b.png".to_string(),
+    ])
+    .set_force_default(true)
+    .set_force_jpeg(true)
+    .set_force_requires_default(true)
+    .set_fragment(Some("top"))
+    .set_hash_display(HashDisplay::new(Some(4), true))
+    .set_hash_length(Some(20))
+    .set_image_size(128)
+    .set_include_file_extension(true)
+    .set_lazy_loading(true)
+    .set_param_prefix("av_")
+    .set_path_style(PathStyle::Bucketed("bucket".to_string()))
+    .set_private(true)
+    .set_rating("pg")
+    .set_request_original(true)
+    .set_retina_suffix(true)
+    .set_scheme("http")
+    .set_size_cap(1024)
+    .set_strip_metadata(true)
+    .set_tenant_salt("tenant")
+    .set_url_template("{scheme}://{host}/{hash}{ext}{query}");
+
+  let restored = Generator::from_bytes(&generator.to_bytes()).unwrap();
+  assert_eq!(restored.to_bytes(), generator.to_bytes());
+  assert_eq!(
+    restored.generate(HOLLLO_EMAIL),
+    generator.generate(HOLLLO_EMAIL)
+  );
+  assert_eq!(
+    restored.generate_srcset(BAUKE_EMAIL),
+    generator.generate_srcset(BAUKE_EMAIL)
+  );
+
+  let default =
+    Generator::from_bytes(&Generator::default().to_bytes()).unwrap();
+  assert_eq!(
+    default.generate(HOLLLO_EMAIL),
+    Generator::default().generate(HOLLLO_EMAIL)
+  );
+
+  assert!(Generator::from_bytes(b"nope").is_err());
+  assert!(Generator::from_bytes(&generator.to_bytes()[..10]).is_err());
+
+  let long_fragment = "a".repeat(70_000);
+  let generator = Generator::default().set_fragment(Some(&long_fragment));
+  let restored = Generator::from_bytes(&generator.to_bytes()).unwrap();
+  assert_eq!(restored.fragment.as_deref(), Some(long_fragment.as_str()));
+}
+
+#[test]
+fn test_combined_identity() {
+  let generator = Generator::default();
+  let phone = "+1 (555) 010-0199";
+
+  let email_only =
+    generator.generate_combined_identity(Some(HOLLLO_EMAIL), None);
+  assert_eq!(email_only, Some(generator.generate(HOLLLO_EMAIL)));
+
+  let phone_only = generator.generate_combined_identity(None, Some(phone));
+  assert_eq!(
+    phone_only,
+    generator.generate_combined_identity(None, Some("+15550100199"))
+  );
+
+  let both =
+    generator.generate_combined_identity(Some(HOLLLO_EMAIL), Some(phone));
+  assert_eq!(
+    both,
+    generator.generate_combined_identity(
+      Some(&HOLLLO_EMAIL.to_uppercase()),
+      Some("+1-555-010-0199")
+    )
+  );
+  assert_ne!(both, email_only);
+  assert_ne!(both, phone_only);
+
+  assert_eq!(generator.generate_combined_identity(None, None), None);
+}
+
+#[test]
+fn test_accessible_img() {
+  let generator = Generator::default().set_image_size(64);
+  let src = generator.generate(HOLLLO_EMAIL);
+
+  assert_eq!(
+    generator.generate_accessible_img(HOLLLO_EMAIL, "Holllo", true),
+    format!(
+      r#"<img src="{src}" width="64" height="64" alt="" aria-hidden="true">"#
+    )
+  );
+
+  assert_eq!(
+    generator.generate_accessible_img(HOLLLO_EMAIL, "\"Holllo\" & co", false),
+    format!(
+      r#"<img src="{src}" width="64" height="64" alt="&quot;Holllo&quot; &amp; co" role="img" aria-label="&quot;Holllo&quot; &amp; co">"#
+    )
+  );
+}
+
+#[test]
+fn test_force_jpeg() {
+  let generator = Generator::default().set_force_jpeg(true).set_image_size(64);
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3.jpg?s=64"
+  );
+}
+
+#[test]
+fn test_hosts_for_plan() {
+  let generator = Generator::default()
+    .set_default_image("https://Example.com:8080/default.png?size=80");
+
+  assert_eq!(
+    generator.hosts_for_plan(false),
+    vec!["www.gravatar.com", "example.com:8080"]
+  );
+  assert_eq!(
+    generator.hosts_for_plan(true),
+    vec!["www.gravatar.com", "example.com:8080", "en.gravatar.com"]
+  );
+
+  let libravatar = Generator::default()
+    .set_base_url("cdn.libravatar.org")
+    .set_default_image("identicon");
+  assert_eq!(libravatar.hosts_for_plan(true), vec!["cdn.libravatar.org"]);
+
+  let pooled = generator.set_fallback_pool(vec![
+    "https://a.example.com/a.png".to_string(),
+    "https://b.example.com/b.png".to_string(),
+  ]);
+  assert_eq!(
+    pooled.hosts_for_plan(false),
+    vec![
+      "www.gravatar.com",
+      "example.com:8080",
+      "a.example.com",
+      "b.example.com"
+    ]
+  );
+}
+
+#[test]
+fn test_etag() {
+  let generator = Generator::default().set_image_size(64);
+  let etag = generator.etag_for(HOLLLO_EMAIL);
+
+  assert_eq!(
+    etag,
+    Generator::default()
+      .set_image_size(64)
+      .etag_for(HOLLLO_EMAIL)
+  );
+  assert!(etag.starts_with('"') && etag.ends_with('"'));
+  assert_ne!(etag, generator.etag_for(BAUKE_EMAIL));
+  assert_ne!(
+    etag,
+    generator.clone().set_image_size(128).etag_for(HOLLLO_EMAIL)
+  );
+
+  let unrelated = generator
+    .clone()
+    .set_csp_nonce(Some("a"))
+    .set_lazy_loading(true)
+    .set_private(true)
+    .set_token_provider(|| "one-time".to_string());
+  assert_eq!(etag, unrelated.etag_for(HOLLLO_EMAIL));
+  assert_eq!(
+    unrelated.etag_for(HOLLLO_EMAIL),
+    unrelated.set_csp_nonce(Some("b")).etag_for(HOLLLO_EMAIL)
+  );
+}
+
+#[test]
+fn test_generate_actor() {
+  let generator = Generator::default().set_base_url("cdn.libravatar.org");
+  let url = generator.generate_actor("https://example.social/users/Holllo");
+
+  assert_eq!(
+    url,
+    generator.generate_actor(" HTTPS://EXAMPLE.Social/users/Holllo ")
+  );
+  assert_ne!(
+    url,
+    generator.generate_actor("https://example.social/users/holllo")
+  );
+  assert_eq!(
+    url,
+    format!(
+      "https://cdn.libravatar.org/avatar/{}",
+      Sha256Hasher.hash("https://example.social/users/Holllo")
+    )
+  );
+}
+
+#[test]
+fn test_size_cap() {
+  let generator = Generator::default().set_size_cap(256).set_image_size(1024);
+  assert!(generator.generate(HOLLLO_EMAIL).ends_with("?s=256"));
+  assert_eq!(generator.query_struct().size, Some(256));
+
+  let urls = generator.resolve_batch(&[
+    (HOLLLO_EMAIL.to_string(), Some(128)),
+    (HOLLLO_EMAIL.to_string(), Some(512)),
+  ]);
+  assert!(urls[0].ends_with("?s=128"));
+  assert!(urls[1].ends_with("?s=256"));
+
+  let generator = Generator::default()
+    .set_size_cap(256)
+    .set_dimensions(1024, 512);
+  assert!(generator.generate(HOLLLO_EMAIL).ends_with("?w=256&h=128"));
+  assert!(generator.assert_roundtrip(HOLLLO_EMAIL).is_ok());
+  assert!(!generator.generate_srcset(HOLLLO_EMAIL).contains("w=512"));
+  assert!(generator
+    .generate_srcset(HOLLLO_EMAIL)
+    .ends_with("?w=256&h=128 2x"));
+
+  for size_cap in [0, -5, 4096] {
+    assert_eq!(
+      Generator::default()
+        .set_size_cap(size_cap)
+        .set_image_size(128)
+        .seal()
+        .unwrap_err(),
+      vec![GeneratorError::InvalidSizeCap(size_cap)]
+    );
+  }
+}
+
+#[test]
+fn test_token_provider() {
+  let counter = std::sync::atomic::AtomicUsize::new(0);
+  let counter = std::sync::Arc::new(counter);
+  let provider_counter = counter.clone();
+
+  let generator =
+    Generator::default()
+      .set_image_size(64)
+      .set_token_provider(move || {
+        let count = provider_counter.fetch_add(1, Ordering::SeqCst);
+        format!("token {count}")
+      });
+
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?s=64&token=token%200"));
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?s=64&token=token%201"));
+  assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_retina_suffix() {
+  let generator = Generator::default()
+    .set_base_url("static.example.com")
+    .set_include_file_extension(true)
+    .set_retina_suffix(true);
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://static.example.com/avatar/ebff9105dce4954b1bdb57fdab079ff3@2x.jpg"
+  );
+  assert!(generator.generate_and_verify(HOLLLO_EMAIL).is_ok());
+}
+
+#[test]
+fn test_assert_roundtrip() {
+  let generators = [
+    Generator::default(),
+    Generator::default().set_base_url("cdn.libravatar.org/mirror"),
+    Generator::default().set_default_image("https://example.com/d.png"),
+    Generator::default()
+      .set_default_image("https://example.com/d.png")
+      .set_default_image_size(64),
+    Generator::default().set_dimensions(160, 90),
+    Generator::default().set_experiment(Some("variant b")),
+    Generator::default().set_force_default(true),
+    Generator::default()
+      .set_force_default(true)
+      .set_force_requires_default(true),
+    Generator::default().set_force_jpeg(true),
+    Generator::default().set_fragment(Some("/profile?tab=1")),
+    Generator::default().set_image_size(128),
+    Generator::default().set_include_file_extension(true),
+    Generator::default()
+      .set_param_prefix("av_")
+      .set_image_size(128)
+      .set_rating("pg"),
+    Generator::default().set_path_style(PathStyle::Sharded),
+    Generator::default().set_path_style(PathStyle::Bucketed("b".to_string())),
+    Generator::default().set_crop_mode(CropMode::Face),
+    Generator::default().set_hash_length(Some(8)),
+    Generator::default()
+      .set_fallback_pool(vec!["https://example.com/a.png".to_string()]),
+    Generator::default()
+      .set_default_image("https://example.com/a b.png")
+      .set_encoding_safe_chars(":/"),
+    Generator::default().set_rating("x"),
+    Generator::default()
+      .set_extension_separator("_thumb.")
+      .set_include_file_extension(true)
+      .set_retina_suffix(true),
+    Generator::default().set_request_original(true),
+    Generator::default().set_strip_metadata(true),
+    Generator::default().set_retina_suffix(true),
+    Generator::default().set_scheme("http"),
+    Generator::default().set_size_cap(64).set_image_size(128),
+    Generator::default().set_token_provider(|| "token".to_string()),
+  ];
+
+  for generator in generators {
+    assert_eq!(
+      generator.assert_roundtrip(HOLLLO_EMAIL),
+      Ok(()),
+      "{generator:?}"
+    );
+  }
+
+  let error = Generator::default()
+    .set_base_url("www.gravatar.com?s=1")
+    .assert_roundtrip(HOLLLO_EMAIL)
+    .unwrap_err();
+  assert!(!error.is_empty());
+}
+
+#[test]
+fn test_generate_for_request() {
+  let generator = Generator::default()
+    .set_base_url("localhost:8080")
+    .set_scheme("http");
+
+  assert_eq!(
+    generator.generate_for_request(HOLLLO_EMAIL, true),
+    "https://localhost:8080/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  );
+  assert_eq!(
+    generator.generate_for_request(HOLLLO_EMAIL, false),
+    "http://localhost:8080/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  );
+
+  let errors = generator.set_scheme("ftp").seal().unwrap_err();
+  assert_eq!(
+    errors,
+    vec![GeneratorError::InvalidScheme("ftp".to_string())]
+  );
+}
+
+#[test]
+fn test_plus_sign_encoding() {
+  let generator = Generator::default()
+    .set_default_image("https://example.com/a+b.png?tags=c+d")
+    .set_rating("pg+");
+
+  let url = generator.generate(HOLLLO_EMAIL);
+  assert!(url.ends_with(
+    "?d=https%3A%2F%2Fexample.com%2Fa%2Bb.png%3Ftags%3Dc%2Bd&r=pg%2B"
+  ));
+  assert!(!url.contains('+'));
+
+  let parsed = ParsedUrl::parse(&url).unwrap();
+  assert_eq!(parsed.query[0].1, "https://example.com/a+b.png?tags=c+d");
+}
+
+#[test]
+fn test_tenant_salt() {
+  let first = Generator::default().set_tenant_salt("tenant-1");
+  let second = Generator::default().set_tenant_salt("tenant-2");
+
+  assert_ne!(first.generate(HOLLLO_EMAIL), second.generate(HOLLLO_EMAIL));
+  assert_ne!(
+    first.generate(HOLLLO_EMAIL),
+    Generator::default().generate(HOLLLO_EMAIL)
+  );
+  assert_eq!(
+    first.generate(HOLLLO_EMAIL),
+    first.generate(&HOLLLO_EMAIL.to_uppercase())
+  );
+  assert_eq!(first.cache_control_for(HOLLLO_EMAIL), "private, max-age=0");
+  assert_eq!(first.assert_roundtrip(HOLLLO_EMAIL), Ok(()));
+
+  assert_eq!(
+    first.generate(HOLLLO_EMAIL),
+    Generator::default()
+      .set_hasher(Box::new(Md5Hasher))
+      .generate(&format!("8:tenant-1{HOLLLO_EMAIL}"))
+  );
+  assert_ne!(
+    Generator::default().set_tenant_salt("a").generate("b@x"),
+    Generator::default().set_tenant_salt("ab").generate("@x")
+  );
+}
+
+#[test]
+fn test_experiment() {
+  let generator = Generator::default()
+    .set_image_size(64)
+    .set_rating("pg")
+    .set_experiment(Some("rounded avatars"));
+
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?s=64&r=pg&exp=rounded%20avatars"));
+
+  let generator = generator.set_experiment(None);
+  assert!(generator.generate(HOLLLO_EMAIL).ends_with("?s=64&r=pg"));
+}
+
+#[test]
+fn test_minimal_url() {
+  for email in [BAUKE_EMAIL, HOLLLO_EMAIL] {
+    assert_eq!(
+      Generator::minimal_url(email),
+      Generator::default().generate(email)
+    );
+    assert!(
+      Generator::minimal_url(email).len()
+        < Generator::default()
+          .set_include_file_extension(true)
+          .generate(email)
+          .len()
+    );
+  }
+}
+
+#[test]
+fn test_localized_default() {
+  let generator =
+    Generator::default().set_default_image("https://example.com/default.png");
+  let expected = |locale: &str| {
+    Generator::default()
+      .set_default_image(&format!(
+        "https://example.com/default.png?locale={locale}"
+      ))
+      .generate(HOLLLO_EMAIL)
+  };
+
+  assert_eq!(
+    generator.generate_localized_default(HOLLLO_EMAIL, "en-US,en;q=0.9"),
+    expected("en-US")
+  );
+  assert_eq!(
+    generator
+      .generate_localized_default(HOLLLO_EMAIL, "fr;q=0.5, nl, de;q=0.8"),
+    expected("nl")
+  );
+
+  for malformed in ["", "*", ";q=1", "en_US", "en;q=abc", "en;q=0"] {
+    assert_eq!(
+      generator.generate_localized_default(HOLLLO_EMAIL, malformed),
+      generator.generate(HOLLLO_EMAIL),
+      "{malformed}"
+    );
+  }
+
+  let keyword = Generator::default().set_default_image("identicon");
+  assert_eq!(
+    keyword.generate_localized_default(HOLLLO_EMAIL, "en-US"),
+    keyword.generate(HOLLLO_EMAIL)
+  );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_hash_many_parallel() {
+  let emails = (0..10_000)
+    .map(|index| format!("user-{index}@example.com"))
+    .collect::<Vec<_>>();
+
+  let sequential = emails
+    .iter()
+    .map(|email| Generator::hash_email(email))
+    .collect::<Vec<_>>();
+
+  assert_eq!(Generator::hash_many_parallel(&emails), sequential);
+  assert_eq!(
+    Generator::hash_many_parallel(&emails[..10]),
+    sequential[..10]
+  );
+  assert!(Generator::hash_many_parallel::<&str>(&[]).is_empty());
+}
+
+#[test]
+fn test_url_template() {
+  let generator = Generator::default()
+    .set_base_url("avatars.example.com")
+    .set_url_template("{scheme}://{host}/v2/{hash}/avatar{ext}{query}")
+    .set_include_file_extension(true)
+    .set_image_size(64)
+    .set_fragment(Some("top"));
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://avatars.example.com/v2/ebff9105dce4954b1bdb57fdab079ff3/avatar.jpg?s=64#top"
+  );
+  assert_eq!(generator.assert_roundtrip(HOLLLO_EMAIL), Ok(()));
+  assert!(generator.generate_and_verify(HOLLLO_EMAIL).is_ok());
+
+  let adjacent = Generator::default()
+    .set_url_template("{scheme}://{host}/{hash}{ext}{query}")
+    .set_base_url("cdn.example.com/avatars")
+    .set_force_jpeg(true)
+    .set_retina_suffix(true)
+    .set_rating("pg")
+    .set_token_provider(|| "t".to_string());
+  assert_eq!(adjacent.assert_roundtrip(HOLLLO_EMAIL), Ok(()));
+
+  let dropped = Generator::default()
+    .set_url_template("{scheme}://{host}/u/{hash}")
+    .set_image_size(64);
+  assert!(dropped.assert_roundtrip(HOLLLO_EMAIL).is_err());
+
+  let errors = generator
+    .set_url_template("{scheme}://{host}/static.jpg")
+    .seal()
+    .unwrap_err();
+  assert_eq!(
+    errors,
+    vec![GeneratorError::InvalidUrlTemplate(
+      "{scheme}://{host}/static.jpg".to_string()
+    )]
+  );
+}
+
+#[test]
+fn test_sizes_attr() {
+  assert_eq!(
+    Generator::sizes_attr(&[(600, 48), (1200, 96)]),
+    "(max-width: 600px) 48px, 96px"
+  );
+  assert_eq!(
+    Generator::sizes_attr(&[(1200, 96), (400, 32), (800, 64)]),
+    "(max-width: 400px) 32px, (max-width: 800px) 64px, 96px"
+  );
+  assert_eq!(Generator::sizes_attr(&[(600, 48)]), "48px");
+  assert_eq!(Generator::sizes_attr(&[]), "");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_generate_json_map() {
+  let generator = Generator::default().set_image_size(64);
+  let map = generator.generate_json_map(&[HOLLLO_EMAIL, BAUKE_EMAIL]);
+
+  assert_eq!(
+    map,
+    serde_json::json!({
+      HOLLLO_EMAIL: generator.generate(HOLLLO_EMAIL),
+      BAUKE_EMAIL: generator.generate(BAUKE_EMAIL),
+    })
+  );
+  assert_eq!(
+    generator.generate_json_map::<&str>(&[]),
+    serde_json::json!({})
+  );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_write_urls() {
+  let generator = Generator::default().set_image_size(64);
+  let mut output = vec![];
+
+  generator
+    .write_urls(&[HOLLLO_EMAIL, BAUKE_EMAIL], &mut output, ", ")
+    .await
+    .unwrap();
+
+  assert_eq!(
+    String::from_utf8(output).unwrap(),
+    format!(
+      "{}, {}, ",
+      generator.generate(HOLLLO_EMAIL),
+      generator.generate(BAUKE_EMAIL)
+    )
+  );
+}
+
+#[test]
+fn test_find_duplicate_avatars() {
+  let emails = [
+    BAUKE_EMAIL,
+    HOLLLO_EMAIL,
+    "unique@example.com",
+    "  Me@Bauke.xyz ",
+    "HELLLO@HOLLLO.CC",
+    BAUKE_EMAIL,
+  ];
+
+  assert_eq!(
+    Generator::find_duplicate_avatars(&emails),
+    vec![vec![0, 3, 5], vec![1, 4]]
+  );
+  assert!(
+    Generator::find_duplicate_avatars(&[BAUKE_EMAIL, HOLLLO_EMAIL]).is_empty()
+  );
+}
+
+#[test]
+fn test_path_style() {
+  let generator = Generator::default().set_base_url("bucket.example.com");
+  let styles = [
+    (PathStyle::Flat, "avatar/ebff9105dce4954b1bdb57fdab079ff3"),
+    (
+      PathStyle::Sharded,
+      "av/eb/ff/ebff9105dce4954b1bdb57fdab079ff3",
+    ),
+    (
+      PathStyle::Bucketed("avatars".to_string()),
+      "avatars/ebff9105dce4954b1bdb57fdab079ff3",
+    ),
+  ];
+
+  for (style, path) in styles {
+    let generator = generator.clone().set_path_style(style);
+    assert_eq!(
+      generator.generate(HOLLLO_EMAIL),
+      format!("https://bucket.example.com/{path}")
+    );
+    assert!(generator.generate_and_verify(HOLLLO_EMAIL).is_ok());
+    assert!(generator.seal().is_ok());
+  }
+
+  for bucket in ["", "a b/?x", "avatars/v2", "a#b"] {
+    let generator = Generator::default()
+      .set_path_style(PathStyle::Bucketed(bucket.to_string()));
+    assert_eq!(
+      generator.seal().unwrap_err(),
+      vec![GeneratorError::InvalidBucket(bucket.to_string())]
+    );
+  }
+}
+
+#[test]
+fn test_override_query() {
+  let generator = Generator::default()
+    .set_default_image("identicon")
+    .set_image_size(64);
+
+  let mut overrides = GeneratorOptions::default();
+  overrides.image_size = Some(256);
+  assert_eq!(generator.override_query(&overrides), "s=256");
+
+  overrides.default_image = Some("identicon".to_string());
+  assert_eq!(generator.override_query(&overrides), "s=256");
+
+  overrides.rating = Some("pg".to_string());
+  assert_eq!(generator.override_query(&overrides), "s=256&r=pg");
+
+  assert_eq!(generator.override_query(&GeneratorOptions::default()), "");
+
+  let counter = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+  let provider_counter = counter.clone();
+  let generator = generator.set_token_provider(move || {
+    provider_counter.fetch_add(1, Ordering::SeqCst).to_string()
+  });
+
+  let mut overrides = GeneratorOptions::default();
+  overrides.image_size = Some(256);
+  assert_eq!(generator.override_query(&overrides), "s=256");
+  assert_eq!(counter.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_generate_object_with_fallback() {
+  let element = Generator::default().generate_object_with_fallback(
+    HOLLLO_EMAIL,
+    "Holllo",
+    96,
+  );
+
+  assert!(element.starts_with(
+    "<object data=\"https://www.gravatar.com/avatar/\
+     ebff9105dce4954b1bdb57fdab079ff3?s=96\" type=\"image/jpeg\""
+  ));
+  assert!(element.contains("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+  assert!(element.contains("fill=\"rgb(235,255,145)\""));
+  assert!(element.contains(">H</text></svg></object>"));
+}
+
+#[test]
+fn test_generate_sticky_mirror() {
+  let generator = Generator::default();
+  let mirrors = ["a.example.com", "b.example.com", "c.example.com"];
+
+  let first = generator.generate_sticky_mirror(HOLLLO_EMAIL, &mirrors);
+  for _ in 0..5 {
+    assert_eq!(
+      generator.generate_sticky_mirror(HOLLLO_EMAIL, &mirrors),
+      first
+    );
+  }
+
+  let used_mirrors = (0..30)
+    .map(|index| {
+      let email = format!("user{index}@example.com");
+      let url = generator.generate_sticky_mirror(&email, &mirrors);
+      mirrors
+        .iter()
+        .position(|mirror| url.starts_with(&format!("https://{mirror}/")))
+        .unwrap()
+    })
+    .collect::<std::collections::HashSet<_>>();
+  assert_eq!(used_mirrors.len(), mirrors.len());
+
+  assert_eq!(
+    generator.generate_sticky_mirror(HOLLLO_EMAIL, &[]),
+    generator.generate(HOLLLO_EMAIL)
+  );
+}
+
+#[test]
+fn test_crop_mode() {
+  let generator = Generator::default()
+    .set_image_size(128)
+    .set_crop_mode(CropMode::Face);
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?s=128&crop=face"
+  );
+
+  assert_eq!("entropy".parse(), Ok(CropMode::Entropy));
+  assert_eq!(
+    "smart".parse::<CropMode>(),
+    Err(GeneratorError::InvalidCropMode("smart".to_string()))
+  );
+}
+
+#[test]
+fn test_hash_length() {
+  let generator = Generator::default().set_hash_length(Some(16));
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b"
+  );
+  assert!(generator.generate_and_verify(HOLLLO_EMAIL).is_ok());
+
+  for length in [0, 15, 34] {
+    let errors = Generator::default()
+      .set_hash_length(Some(length))
+      .seal()
+      .unwrap_err();
+    assert_eq!(errors, vec![GeneratorError::InvalidHashLength(length)]);
+  }
+
+  assert!(Generator::default()
+    .set_hasher(Box::new(Sha256Hasher))
+    .set_hash_length(Some(64))
+    .seal()
+    .is_ok());
+
+  let sharded = Generator::default()
+    .set_hash_length(Some(2))
+    .set_path_style(PathStyle::Sharded)
+    .set_retina_suffix(true);
+  assert_eq!(
+    sharded.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/av/eb/ff/eb@2x"
+  );
+  assert_eq!(sharded.assert_roundtrip(HOLLLO_EMAIL), Ok(()));
+}
+
+#[test]
+fn test_render_info() {
+  let generator = Generator::default().set_image_size(64);
+  let info = generator.render_info(HOLLLO_EMAIL, "holllo helllo");
+
+  assert_eq!(info.url, generator.generate(HOLLLO_EMAIL));
+  assert_eq!(
+    format!("\"{}\"", info.cache_key),
+    generator.etag_for(HOLLLO_EMAIL)
+  );
+  assert_eq!(info.placeholder_color, (235, 255, 145));
+  assert_eq!(info.initials, "HH");
+
+  assert_eq!(generator.render_info(HOLLLO_EMAIL, "  ").initials, "?");
+}
+
+#[test]
+fn test_generate_respecting_dnt() {
+  let generator = Generator::default().set_image_size(48);
+
+  assert_eq!(
+    generator.generate_respecting_dnt(HOLLLO_EMAIL, false),
+    generator.generate(HOLLLO_EMAIL)
+  );
+
+  let url = generator.generate_respecting_dnt("John.Doe@example.com", true);
+  assert!(url.starts_with("data:image/svg+xml,%3Csvg%20"));
+  assert!(!url.contains("gravatar.com"));
+
+  let svg = urlencoding::decode(&url["data:image/svg+xml,".len()..]).unwrap();
+  assert!(svg.contains("width=\"48\" height=\"48\""));
+  assert!(svg.contains(">JD</text>"));
+
+  let url = Generator::default()
+    .set_image_size(-1)
+    .generate_respecting_dnt(HOLLLO_EMAIL, true);
+  let svg = urlencoding::decode(&url["data:image/svg+xml,".len()..]).unwrap();
+  assert!(svg.contains("width=\"1\" height=\"1\""));
+}
+
+#[test]
+fn test_csp_nonce() {
+  let generator = Generator::default().set_image_size(32);
+  assert!(!generator
+    .generate_img_tag(HOLLLO_EMAIL, "Holllo")
+    .contains("nonce="));
+
+  let element = generator
+    .set_csp_nonce(Some("a\"b"))
+    .generate_img_tag(HOLLLO_EMAIL, "Holllo");
+  assert_eq!(
+    element,
+    "<img src=\"https://www.gravatar.com/avatar/\
+     ebff9105dce4954b1bdb57fdab079ff3?s=32\" width=\"32\" height=\"32\" \
+     alt=\"Holllo\" nonce=\"a&quot;b\">"
+  );
+}
+
+#[test]
+fn test_url_or_local_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let generator =
+    Generator::default().set_local_fallback_dir(dir.path().to_path_buf());
+
+  assert_eq!(
+    generator.url_or_local_file(HOLLLO_EMAIL),
+    generator.generate(HOLLLO_EMAIL)
+  );
+
+  let path = dir.path().join("ebff9105dce4954b1bdb57fdab079ff3.jpg");
+  std::fs::write(&path, b"jpeg").unwrap();
+
+  let url = generator.url_or_local_file(HOLLLO_EMAIL);
+  assert!(url.starts_with("file:///"));
+  assert!(url.ends_with("/ebff9105dce4954b1bdb57fdab079ff3.jpg"));
+  assert_eq!(
+    generator.url_or_local_file(BAUKE_EMAIL),
+    generator.generate(BAUKE_EMAIL)
+  );
+
+  let relative = tempfile::Builder::new()
+    .prefix("local avatars")
+    .tempdir_in(".")
+    .unwrap();
+  std::fs::write(
+    relative.path().join("ebff9105dce4954b1bdb57fdab079ff3.jpg"),
+    b"jpeg",
+  )
+  .unwrap();
+
+  let url = Generator::default()
+    .set_local_fallback_dir(relative.path().to_path_buf())
+    .url_or_local_file(HOLLLO_EMAIL);
+  assert!(url.starts_with("file:///"));
+  assert!(!url.contains("/./"));
+  assert!(url.contains("/local%20avatars"));
+  assert!(url.ends_with("/ebff9105dce4954b1bdb57fdab079ff3.jpg"));
+}
+
+#[test]
+fn test_placeholder_seed() {
+  let seed = Generator::placeholder_seed(HOLLLO_EMAIL);
+  assert_eq!(seed, 0xebff9105dce4954b);
+  assert_eq!(Generator::placeholder_seed("HELLLO@HOLLLO.CC"), seed);
+  assert_ne!(Generator::placeholder_seed(BAUKE_EMAIL), seed);
+}
+
+#[test]
+fn test_strip_metadata() {
+  let generator = Generator::default()
+    .set_image_size(64)
+    .set_strip_metadata(true);
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?s=64&strip=1"
+  );
+  assert!(!generator
+    .set_strip_metadata(false)
+    .generate(HOLLLO_EMAIL)
+    .contains("strip="));
+}
+
+#[test]
+fn test_generate_by_rating() {
+  let generator = Generator::default().set_image_size(64).set_rating("g");
+  let urls = generator.generate_by_rating(HOLLLO_EMAIL);
+
+  let expected = [
+    (Rating::G, "g"),
+    (Rating::Pg, "pg"),
+    (Rating::R, "r"),
+    (Rating::X, "x"),
+  ];
+  assert_eq!(urls.len(), expected.len());
+
+  for ((rating, url), (expected_rating, value)) in
+    urls.into_iter().zip(expected)
+  {
+    assert_eq!(rating, expected_rating);
+    assert_eq!(
+      url,
+      format!(
+        "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+         ?s=64&r={value}"
+      )
+    );
+  }
+}
+
+#[test]
+fn test_display_hash() {
+  let generator = Generator::default();
+  assert_eq!(
+    generator.display_hash(HOLLLO_EMAIL),
+    Generator::hash_email(HOLLLO_EMAIL)
+  );
+
+  let generator = generator.set_hash_display(HashDisplay::new(Some(4), false));
+  assert_eq!(
+    generator.display_hash(HOLLLO_EMAIL),
+    "ebff 9105 dce4 954b 1bdb 57fd ab07 9ff3"
+  );
+
+  let generator = generator.set_hash_display(HashDisplay::new(None, true));
+  assert_eq!(
+    generator.display_hash(HOLLLO_EMAIL),
+    "EBFF9105DCE4954B1BDB57FDAB079FF3"
+  );
+
+  let generator = generator
+    .set_hash_display(HashDisplay::new(Some(4), false))
+    .set_hash_length(Some(16));
+  assert_eq!(generator.display_hash(HOLLLO_EMAIL), "ebff 9105 dce4 954b");
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("/ebff9105dce4954b"));
+}
+
+#[test]
+fn test_request_original() {
+  let generator = Generator::default()
+    .set_default_image("identicon")
+    .set_request_original(true);
+
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?d=identicon&original=1"
+  );
+  assert!(generator.clone().seal().is_ok());
+
+  let generator = generator.set_image_size(128);
+  assert!(!generator.generate(HOLLLO_EMAIL).contains("s=128"));
+  assert_eq!(
+    generator.seal().unwrap_err(),
+    vec![GeneratorError::OriginalWithImageSize]
+  );
+}
+
+#[test]
+fn test_fallback_pool() {
+  let pool = (0..4)
+    .map(|index| format!("https://example.com/{index}.png"))
+    .collect::<Vec<_>>();
+  let generator = Generator::default()
+    .set_default_image("identicon")
+    .set_fallback_pool(pool.clone());
+
+  let url = generator.generate(HOLLLO_EMAIL);
+  assert_eq!(generator.generate(HOLLLO_EMAIL), url);
+  assert!(!url.contains("d=identicon"));
+
+  let picked = (0..20)
+    .map(|index| {
+      let url = generator.generate(&format!("user{index}@example.com"));
+      pool
+        .iter()
+        .position(|image| {
+          url.ends_with(&format!("?d={}", urlencoding::encode(image)))
+        })
+        .unwrap()
+    })
+    .collect::<std::collections::HashSet<_>>();
+  assert!(picked.len() > 1);
+
+  assert_eq!(generator.warnings().len(), 1);
+  assert_eq!(generator.query_struct().default_image, None);
+  assert!(!generator.query_parameters().contains("d="));
+
+  let mut overrides = GeneratorOptions::default();
+  overrides.default_image = Some("retro".to_string());
+  overrides.image_size = Some(64);
+  assert_eq!(generator.override_query(&overrides), "s=64");
+
+  let localized =
+    generator.generate_localized_default(HOLLLO_EMAIL, "nl, en;q=0.5");
+  assert!(localized.starts_with(url.as_str()));
+  assert!(localized.ends_with("%3Flocale%3Dnl"));
+
+  assert!(Generator::default()
+    .set_fallback_pool(vec!["not a url".to_string()])
+    .seal()
+    .is_err());
+}
+
+#[test]
+fn test_generate_with_metrics() {
+  let generator = Generator::default()
+    .set_default_image("identicon")
+    .set_image_size(64);
+  let (url, metrics) = generator.generate_with_metrics(HOLLLO_EMAIL);
+
+  assert_eq!(url, generator.generate(HOLLLO_EMAIL));
+  assert_eq!(metrics.url_length, url.len());
+  assert!(metrics.total_duration >= metrics.hash_duration);
+  assert!(metrics.total_duration >= metrics.query_duration);
+}
+
+#[test]
+fn test_immutable_url() {
+  let generator = Generator::default();
+  assert_eq!(
+    generator.immutable_url(HOLLLO_EMAIL, "abc123"),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?ic=abc123"
+  );
+
+  let generator = generator
+    .set_image_size(64)
+    .set_param_prefix("av_")
+    .set_fragment(Some("top"));
+  assert_eq!(
+    generator.immutable_url(HOLLLO_EMAIL, "abc123"),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?av_s=64&av_ic=abc123#top"
+  );
+}
+
+#[test]
+fn test_resource_name() {
+  let generator = Generator::default()
+    .set_base_url("avatars.example.com")
+    .set_default_image("identicon")
+    .set_fragment(Some("top"))
+    .set_token_provider(|| "secret".to_string());
+  let name = generator.resource_name(HOLLLO_EMAIL);
+
+  assert_eq!(
+    name,
+    "https://avatars.example.com/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  );
+  assert!(!name.contains(HOLLLO_EMAIL));
+  assert!(!name.contains("secret"));
+  assert_eq!(generator.resource_name(HOLLLO_EMAIL), name);
+}
+
+#[test]
+fn test_base_url_lowercase_host() {
+  let generator = Generator::default().set_base_url("WWW.Gravatar.COM");
+  assert_eq!(generator.base_url, "www.gravatar.com");
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3"
+  );
+
+  let generator =
+    Generator::default().set_base_url("Mirror.Example.com/Avatars");
+  assert_eq!(generator.base_url, "mirror.example.com/Avatars");
+}
+
+#[test]
+fn test_newsletter_avatar() {
+  let generator = Generator::default().set_scheme("http").set_image_size(48);
+  let url = generator.newsletter_avatar(HOLLLO_EMAIL, "launch&week=1");
+
+  assert_eq!(
+    url,
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3.jpg\
+     ?s=48&c=launch%26week%3D1"
+  );
+  assert_eq!(generator.scheme, "http");
+}
+
+#[test]
+fn test_dns_prefetch_hosts() {
+  let generator = Generator::default()
+    .set_default_image("https://fallback.example.com/a.png")
+    .set_fallback_pool(vec![
+      "https://pool.example.com/a.png".to_string(),
+      "https://fallback.example.com/b.png".to_string(),
+    ]);
+
+  assert_eq!(
+    generator.dns_prefetch_hosts(),
+    [
+      "www.gravatar.com",
+      "fallback.example.com",
+      "pool.example.com",
+      "en.gravatar.com"
+    ]
+  );
+}
+
+#[test]
+fn test_encoding_safe_chars() {
+  let generator =
+    Generator::default().set_default_image("https://example.com/a b.png?v=1");
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?d=https%3A%2F%2Fexample.com%2Fa%20b.png%3Fv%3D1"));
+
+  let generator = generator.set_encoding_safe_chars(":/");
+  assert!(generator
+    .generate(HOLLLO_EMAIL)
+    .ends_with("?d=https://example.com/a%20b.png%3Fv%3D1"));
+}
+
+#[test]
+fn test_generate_for_user_agent() {
+  let generator = Generator::default().set_image_size(64);
+  let browser = "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Firefox/115.0";
+  let crawler = "Mozilla/5.0 (compatible; Bingbot/2.0)";
+
+  assert_eq!(
+    generator.generate_for_user_agent(HOLLLO_EMAIL, browser),
+    generator.generate(HOLLLO_EMAIL)
+  );
+  assert_eq!(
+    generator.generate_for_user_agent(HOLLLO_EMAIL, crawler),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?d=mp&f=y&s=64"
+  );
+
+  let generator = generator
+    .set_default_image("identicon")
+    .set_crawler_user_agents(vec!["Firefox".to_string()]);
+  assert!(generator
+    .generate_for_user_agent(HOLLLO_EMAIL, browser)
+    .contains("?d=identicon&f=y&"));
+  assert!(!generator
+    .generate_for_user_agent(HOLLLO_EMAIL, crawler)
+    .contains("f=y"));
+}
+
+#[test]
+fn test_generate_with_noscript() {
+  let html = Generator::default()
+    .set_image_size(40)
+    .generate_with_noscript(HOLLLO_EMAIL, "<Holllo>");
+  let src = "https://www.gravatar.com/avatar/\
+             ebff9105dce4954b1bdb57fdab079ff3?s=40";
+
+  assert_eq!(
+    html,
+    format!(
+      "<img data-src=\"{src}\" width=\"40\" height=\"40\" \
+       class=\"lazyload\" alt=\"&lt;Holllo&gt;\">\
+       <noscript><img src=\"{src}\" width=\"40\" height=\"40\" \
+       alt=\"&lt;Holllo&gt;\"></noscript>"
+    )
+  );
+}
+
+#[test]
+fn test_lazy_loading() {
+  let generator = Generator::default();
+  let element = generator.generate_img_tag(HOLLLO_EMAIL, "Holllo");
+  assert!(!element.contains("loading="));
+  assert!(!element.contains("decoding="));
+
+  let element = generator
+    .set_lazy_loading(true)
+    .set_csp_nonce(Some("r4nd0m"))
+    .generate_img_tag(HOLLLO_EMAIL, "Holllo");
+  assert!(element.ends_with(
+    "alt=\"Holllo\" loading=\"lazy\" decoding=\"async\" nonce=\"r4nd0m\">"
+  ));
+}
+
+#[test]
+fn test_cdn_object_key() {
+  let generator = Generator::default()
+    .set_image_size(256)
+    .set_include_file_extension(true);
+  assert_eq!(
+    generator.cdn_object_key(HOLLLO_EMAIL),
+    "avatars/256/ebff9105dce4954b1bdb57fdab079ff3.jpg"
+  );
+
+  assert_eq!(
+    Generator::default().cdn_object_key(HOLLLO_EMAIL),
+    "avatars/80/ebff9105dce4954b1bdb57fdab079ff3"
+  );
+  assert_eq!(
+    Generator::default()
+      .set_dimensions(160, 90)
+      .set_force_jpeg(true)
+      .cdn_object_key(HOLLLO_EMAIL),
+    "avatars/160x90/ebff9105dce4954b1bdb57fdab079ff3.jpg"
+  );
+}
+
+#[test]
+fn test_extension_separator() {
+  let generator = Generator::default()
+    .set_include_file_extension(true)
+    .set_extension_separator("_thumb.");
+  assert_eq!(
+    generator.generate(HOLLLO_EMAIL),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3_thumb.jpg"
+  );
+  assert!(generator.generate_and_verify(HOLLLO_EMAIL).is_ok());
+
+  assert!(Generator::default()
+    .set_extension_separator("-")
+    .generate(HOLLLO_EMAIL)
+    .ends_with("ff3"));
+
+  for separator in ["", "/", "?x."] {
+    assert_eq!(
+      Generator::default()
+        .set_extension_separator(separator)
+        .seal()
+        .unwrap_err(),
+      vec![GeneratorError::InvalidExtensionSeparator(
+        separator.to_string()
+      )]
+    );
+  }
+}
+
+#[test]
+fn test_generate_and_cache() {
+  let generator = Generator::default().set_image_size(64);
+  let mut cache = std::collections::HashMap::new();
+
+  let url = generator.generate_and_cache(HOLLLO_EMAIL, &mut cache);
+  assert_eq!(url, generator.generate(HOLLLO_EMAIL));
+  assert_eq!(cache.len(), 1);
+
+  let etag = generator.etag_for(HOLLLO_EMAIL);
+  assert_eq!(cache.get(etag.trim_matches('"')), Some(&url));
+
+  generator.generate_and_cache(BAUKE_EMAIL, &mut cache);
+  assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_pastel_color() {
+  assert_eq!(
+    Generator::pastel_color(HOLLLO_EMAIL),
+    Generator::pastel_color(" HELLLO@holllo.cc")
+  );
+
+  for index in 0..50 {
+    let (red, green, blue) =
+      Generator::pastel_color(&format!("user{index}@example.com"));
+    let (min, max) = (red.min(green).min(blue), red.max(green).max(blue));
+    assert!(min >= 190, "{index}: {red}, {green}, {blue}");
+    assert!(max - min <= 40, "{index}: {red}, {green}, {blue}");
+  }
+}
+
+#[test]
+fn test_face_thumbnail_url() {
+  let generator = Generator::default().set_dimensions(160, 90);
+  assert_eq!(
+    generator.face_thumbnail_url(HOLLLO_EMAIL, 96),
+    "https://www.gravatar.com/avatar/ebff9105dce4954b1bdb57fdab079ff3\
+     ?s=96&crop=face"
+  );
+
+  assert!(generator
+    .face_thumbnail_url(HOLLLO_EMAIL, 10_000)
+    .ends_with("?s=2048&crop=face"));
+  assert!(generator
+    .face_thumbnail_url(HOLLLO_EMAIL, 0)
+    .ends_with("?s=1&crop=face"));
+}
+
+#[test]
+fn test_diagnostics() {
+  let report = Generator::default()
+    .set_base_url("cdn.libravatar.org")
+    .set_crop_mode(CropMode::Face)
+    .set_force_default(true)
+    .set_image_size(128)
+    .set_rating("pg")
+    .set_tenant_salt("secret-salt")
+    .set_csp_nonce(Some("n0nce"))
+    .set_token_provider(|| "s3cret".to_string())
+    .diagnostics(HOLLLO_EMAIL);
+
+  assert!(!report.contains(HOLLLO_EMAIL));
+  assert!(!report.contains("secret-salt"));
+  assert!(!report.contains("n0nce"));
+  assert!(!report.contains("s3cret"));
+  insta::assert_snapshot!("diagnostics", report);
+}